@@ -0,0 +1,110 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+//! Measures download throughput and per request latency of served files.
+//! Each is measured with and without `zero_copy`.
+//! Run with `cargo bench --bench throughput`.
+
+use std::{
+    io,
+    net::{Ipv4Addr, SocketAddr},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use direct_share::{app, config::DirectShareConfig, DirectShare};
+use tokio::{
+    fs,
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    spawn,
+};
+
+const LARGE_FILE_SIZE: usize = 256 * 1024 * 1024;
+const LARGE_ROUNDS: u32 = 5;
+
+const SMALL_FILE_SIZE: usize = 1024;
+const SMALL_ROUNDS: u32 = 1000;
+
+#[tokio::main]
+async fn main() -> io::Result<()> {
+    let dir = std::env::temp_dir().join("direct_share_bench");
+    fs::create_dir_all(&dir).await?;
+
+    let large = dir.join("large.bin");
+    let small = dir.join("small.bin");
+    fs::write(&large, vec![0x5a; LARGE_FILE_SIZE]).await?;
+    fs::write(&small, vec![0x5a; SMALL_FILE_SIZE]).await?;
+
+    for zero_copy in [false, true] {
+        let config = DirectShareConfig {
+            enable_upnp: false,
+            show_qr: false,
+            zero_copy,
+            ..Default::default()
+        };
+
+        let share = DirectShare::builder(config).build();
+        let large_key = share.register(large.clone());
+        let small_key = share.register(small.clone());
+
+        let listener = app::bind(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0))?;
+        let addr = listener.local_addr()?;
+        spawn(share.serve(listener));
+
+        println!("zero_copy: {zero_copy}");
+
+        let elapsed = bench(addr, &large_key, LARGE_ROUNDS, LARGE_FILE_SIZE).await?;
+        let bytes = LARGE_FILE_SIZE as f64 * LARGE_ROUNDS as f64;
+        println!(
+            "large file: {LARGE_ROUNDS} x {} MiB, {:.1} MB/s",
+            LARGE_FILE_SIZE / 1024 / 1024,
+            bytes / elapsed.as_secs_f64() / 1_000_000.0
+        );
+
+        let elapsed = bench(addr, &small_key, SMALL_ROUNDS, SMALL_FILE_SIZE).await?;
+        println!(
+            "small file: {SMALL_ROUNDS} x {SMALL_FILE_SIZE} B, {:.3} ms/request",
+            elapsed.as_secs_f64() * 1000.0 / SMALL_ROUNDS as f64
+        );
+    }
+
+    fs::remove_dir_all(Path::new(&dir)).await
+}
+
+/// Download `key` `rounds` times sequentially and return total time
+async fn bench(addr: SocketAddr, key: &str, rounds: u32, size: usize) -> io::Result<Duration> {
+    let mut buf = Vec::with_capacity(size + 1024);
+
+    let start = Instant::now();
+    for _ in 0..rounds {
+        buf.clear();
+        download(addr, key, &mut buf).await?;
+
+        if buf.len() < size {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "incomplete response",
+            ));
+        }
+    }
+
+    Ok(start.elapsed())
+}
+
+/// Download `key` into `buf` including response headers
+async fn download(addr: SocketAddr, key: &str, buf: &mut Vec<u8>) -> io::Result<()> {
+    let mut stream = TcpStream::connect(addr).await?;
+    stream
+        .write_all(
+            format!("GET /{key} HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\n\r\n").as_bytes(),
+        )
+        .await?;
+
+    stream.read_to_end(buf).await?;
+
+    Ok(())
+}
@@ -0,0 +1,120 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::{
+    ffi::OsString,
+    io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use hyper::{Method, StatusCode};
+use tokio::{
+    fs::{self, File, OpenOptions},
+    io::{AsyncWriteExt, BufWriter},
+    spawn,
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+};
+
+#[derive(Debug, Clone)]
+/// Resolved file path of the response, stored in response extensions
+pub struct ResolvedPath(pub PathBuf);
+
+#[derive(Debug, Clone)]
+/// Access log written to file in background
+pub struct AccessLog {
+    sender: UnboundedSender<String>,
+}
+
+impl AccessLog {
+    /// Start writing access log to `path`.
+    /// The file is rolled to `{path}.1` once it exceeds `rotate_size` bytes.
+    pub fn open(path: PathBuf, rotate_size: u64) -> Self {
+        let (sender, receiver) = unbounded_channel();
+
+        spawn(async move {
+            if let Err(err) = write_log(&path, rotate_size, receiver).await {
+                log::error!(
+                    "cannot write access log path: {} err: {err}",
+                    path.display()
+                );
+            }
+        });
+
+        Self { sender }
+    }
+
+    /// Record a request. `bytes` is `None` if the response size is unknown.
+    pub fn record(
+        &self,
+        addr: SocketAddr,
+        method: &Method,
+        path: Option<&Path>,
+        status: StatusCode,
+        bytes: Option<u64>,
+    ) {
+        let line = format!(
+            "{addr} [{}] {method} {} {} {}\n",
+            httpdate::fmt_http_date(SystemTime::now()),
+            path.map(|path| path.to_string_lossy())
+                .unwrap_or("-".into()),
+            status.as_u16(),
+            bytes.map(|bytes| bytes.to_string()).unwrap_or("-".into()),
+        );
+
+        // Writer task is gone only if the log file is unusable
+        let _ = self.sender.send(line);
+    }
+}
+
+async fn write_log(
+    path: &Path,
+    rotate_size: u64,
+    mut receiver: UnboundedReceiver<String>,
+) -> io::Result<()> {
+    let (mut writer, mut size) = open_log(path).await?;
+
+    while let Some(line) = receiver.recv().await {
+        let mut next = Some(line);
+
+        // Write every pending line before flushing
+        while let Some(line) = next.take() {
+            if size > 0 && size + line.len() as u64 > rotate_size {
+                writer.flush().await?;
+                rotate(path).await?;
+                (writer, size) = open_log(path).await?;
+            }
+
+            writer.write_all(line.as_bytes()).await?;
+            size += line.len() as u64;
+
+            next = receiver.try_recv().ok();
+        }
+
+        writer.flush().await?;
+    }
+
+    Ok(())
+}
+
+async fn open_log(path: &Path) -> io::Result<(BufWriter<File>, u64)> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    let size = file.metadata().await?.len();
+
+    Ok((BufWriter::new(file), size))
+}
+
+async fn rotate(path: &Path) -> io::Result<()> {
+    let mut rotated = OsString::from(path.as_os_str());
+    rotated.push(".1");
+
+    fs::rename(path, rotated).await
+}
@@ -0,0 +1,111 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use tokio::{
+    sync::Notify,
+    time::{sleep_until, Instant},
+};
+
+#[derive(Debug)]
+/// Tracks open connections and time of last activity
+pub struct Activity {
+    active: AtomicUsize,
+    last: Mutex<Instant>,
+    closed: Notify,
+}
+
+impl Activity {
+    pub fn new() -> Self {
+        Self {
+            active: AtomicUsize::new(0),
+            last: Mutex::new(Instant::now()),
+            closed: Notify::new(),
+        }
+    }
+
+    /// Mark a connection open until the guard is dropped
+    pub fn enter(self: &Arc<Self>) -> ActivityGuard {
+        self.active.fetch_add(1, Ordering::AcqRel);
+
+        ActivityGuard {
+            activity: self.clone(),
+        }
+    }
+
+    /// Number of open connections
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::Acquire)
+    }
+
+    /// Wait until no connection was open for `duration`
+    pub async fn wait_idle(&self, duration: Duration) {
+        loop {
+            if self.active.load(Ordering::Acquire) != 0 {
+                self.closed.notified().await;
+                continue;
+            }
+
+            let deadline = *self.last.lock().unwrap() + duration;
+            if Instant::now() >= deadline {
+                return;
+            }
+
+            sleep_until(deadline).await;
+        }
+    }
+}
+
+impl Default for Activity {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+/// Open connection of [`Activity`]
+pub struct ActivityGuard {
+    activity: Arc<Activity>,
+}
+
+impl Drop for ActivityGuard {
+    fn drop(&mut self) {
+        *self.activity.last.lock().unwrap() = Instant::now();
+        self.activity.active.fetch_sub(1, Ordering::AcqRel);
+        self.activity.closed.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use tokio::time::Instant;
+
+    use crate::activity::Activity;
+
+    #[tokio::test(start_paused = true)]
+    pub async fn wait_idle_test() {
+        let activity = Arc::new(Activity::new());
+        let start = Instant::now();
+
+        let guard = activity.enter();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            drop(guard);
+        });
+
+        activity.wait_idle(Duration::from_secs(10)).await;
+        assert_eq!(start.elapsed().as_secs(), 40);
+    }
+}
@@ -0,0 +1,179 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::{io, path::PathBuf, sync::RwLock, time::Duration};
+
+use http_body_util::{combinators::BoxBody, BodyExt, Empty, Full, Limited};
+use hyper::{body::Bytes, header, HeaderMap, Method, Request, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::{
+    auth::constant_time_eq,
+    cache,
+    map::{LinkOptions, PathMap},
+    pause::Pause,
+};
+
+/// Maximum size of admin request body
+const MAX_BODY_SIZE: usize = 65536;
+
+#[derive(Debug, Deserialize)]
+struct RegisterRequest {
+    path: PathBuf,
+
+    #[serde(default)]
+    ttl_secs: Option<u64>,
+
+    #[serde(default)]
+    download_limit: Option<u32>,
+
+    #[serde(default)]
+    password: Option<String>,
+}
+
+/// Handle admin api request. `route` is the request path after `admin/`
+pub async fn handle(
+    route: &str,
+    token: &str,
+    map: &RwLock<PathMap>,
+    pause: &Pause,
+    req: Request<hyper::body::Incoming>,
+) -> Response<BoxBody<Bytes, io::Error>> {
+    if !is_authorized(req.headers(), token) {
+        return status_response(StatusCode::FORBIDDEN);
+    }
+
+    match (req.method().clone(), route) {
+        (Method::POST, "register") => register(map, req).await,
+
+        (Method::POST, "pause") => set_paused(pause, true),
+        (Method::POST, "resume") => set_paused(pause, false),
+
+        (Method::DELETE, route) => match route.strip_prefix("unregister/") {
+            Some(key) => unregister(map, key),
+            None => status_response(StatusCode::NOT_FOUND),
+        },
+
+        _ => status_response(StatusCode::NOT_FOUND),
+    }
+}
+
+/// Check if the request carries admin token as bearer authorization
+pub fn is_authorized(headers: &HeaderMap, token: &str) -> bool {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|value| constant_time_eq(value.trim().as_bytes(), token.as_bytes()))
+}
+
+async fn register(
+    map: &RwLock<PathMap>,
+    req: Request<hyper::body::Incoming>,
+) -> Response<BoxBody<Bytes, io::Error>> {
+    let body = match Limited::new(req.into_body(), MAX_BODY_SIZE).collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(err) => {
+            log::warn!("cannot read admin request body err: {err}");
+            return status_response(StatusCode::BAD_REQUEST);
+        }
+    };
+
+    let request = match serde_json::from_slice::<RegisterRequest>(&body) {
+        Ok(request) => request,
+        Err(err) => {
+            log::warn!("invalid admin register request err: {err}");
+            return status_response(StatusCode::BAD_REQUEST);
+        }
+    };
+
+    // File is hashed before locking the map so lookups are not blocked while reading it
+    let hashes_content = map.read().unwrap().hashes_content();
+    let digest = if hashes_content
+        && fs::metadata(&request.path)
+            .await
+            .is_ok_and(|meta| meta.is_file())
+    {
+        cache::sha256_file(&request.path)
+            .await
+            .inspect_err(|err| {
+                log::warn!(
+                    "cannot hash content, using path instead path: {} err: {err}",
+                    request.path.display()
+                )
+            })
+            .ok()
+    } else {
+        None
+    };
+
+    let registration = match map.write().unwrap().register_info_with_digest(
+        &request.path,
+        digest,
+        &LinkOptions {
+            ttl: request.ttl_secs.map(Duration::from_secs),
+            download_limit: request.download_limit,
+            password: request.password,
+        },
+    ) {
+        Ok(registration) => registration,
+        Err(err) => {
+            log::warn!(
+                "admin register rejected path: {} err: {err}",
+                request.path.display()
+            );
+            return status_response(StatusCode::BAD_REQUEST);
+        }
+    };
+    log::info!(
+        "registered {registration} key: {} via admin api",
+        registration.key
+    );
+
+    json_response(StatusCode::CREATED, &registration)
+}
+
+fn unregister(map: &RwLock<PathMap>, key: &str) -> Response<BoxBody<Bytes, io::Error>> {
+    match map.write().unwrap().unregister(key) {
+        Some(target) => {
+            log::info!("unregistered {target} key: {key} via admin api");
+            status_response(StatusCode::NO_CONTENT)
+        }
+
+        None => status_response(StatusCode::NOT_FOUND),
+    }
+}
+
+fn set_paused(pause: &Pause, paused: bool) -> Response<BoxBody<Bytes, io::Error>> {
+    if pause.set(paused) {
+        log::info!("paused state changed via admin api paused: {paused}");
+    }
+
+    status_response(StatusCode::NO_CONTENT)
+}
+
+fn json_response<T: Serialize>(
+    status: StatusCode,
+    value: &T,
+) -> Response<BoxBody<Bytes, io::Error>> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(
+            Full::new(Bytes::from(serde_json::to_vec(value).unwrap()))
+                .map_err(|_| unreachable!())
+                .boxed(),
+        )
+        .unwrap()
+}
+
+fn status_response(status: StatusCode) -> Response<BoxBody<Bytes, io::Error>> {
+    Response::builder()
+        .status(status)
+        .body(Empty::<Bytes>::new().map_err(|_| unreachable!()).boxed())
+        .unwrap()
+}
@@ -0,0 +1,1937 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::{
+    convert::Infallible,
+    fs::Metadata,
+    io::{self, Cursor, SeekFrom},
+    net::{IpAddr, SocketAddr},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::{Duration, Instant, SystemTime},
+};
+
+use async_compression::tokio::bufread::{BrotliEncoder, GzipEncoder};
+use futures_util::{FutureExt, TryStreamExt};
+use http_body_util::{combinators::BoxBody, BodyExt, Empty, Full, StreamBody};
+use hyper::{
+    body::{Body, Bytes, Frame},
+    header::{self, HeaderName, HeaderValue},
+    server::conn::http1,
+    service::service_fn,
+    Method, Request, Response, StatusCode,
+};
+use hyper_util::rt::{TokioIo, TokioTimer};
+use never_say_never::Never;
+use socket2::{Domain, Protocol, Socket, Type};
+use tokio::{
+    fs::{self, File},
+    io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, BufReader},
+    net::TcpListener,
+    runtime::{Handle, RuntimeFlavor},
+    spawn,
+    sync::Semaphore,
+    task::block_in_place,
+    time::sleep,
+};
+use tokio_rustls::TlsAcceptor;
+use tokio_util::io::ReaderStream;
+
+use crate::{
+    access_log::{AccessLog, ResolvedPath},
+    activity::Activity,
+    admin,
+    archive::{ArchiveSource, SizeLimit, WalkOptions},
+    archive_cache::ArchiveCache,
+    auth, cache,
+    config::{ArchiveFormat, DirectShareConfig, DirectoryMode, Favicon},
+    constants,
+    digest::{self, DigestCache},
+    disposition, download_page,
+    exclude::ExcludeRules,
+    forwarded, landing,
+    listener::{Accept, Listener},
+    listing,
+    map::{self, KeyError, LinkOptions, PathMap, Registration, Target},
+    media::{self, Encoding},
+    metrics::Metrics,
+    pause::Pause,
+    range::{self, parse_range, ByteRange},
+    request_limit::RequestLimiter,
+    sized::SizedReader,
+    throttle::{RateLimiter, Throttle},
+    timeout::IdleTimeout,
+    transfer::{EndBody, LoggedBody},
+    upload, util,
+    zero_copy::{self, SendFileSlot},
+};
+
+/// Builder of [`DirectShare`]
+pub struct DirectShareBuilder {
+    config: DirectShareConfig,
+    map: Option<PathMap>,
+    acceptor: Option<TlsAcceptor>,
+}
+
+impl DirectShareBuilder {
+    pub fn new(config: DirectShareConfig) -> Self {
+        Self {
+            config,
+            map: None,
+            acceptor: None,
+        }
+    }
+
+    /// Serve links of existing map, such as one restored from saved links
+    pub fn map(mut self, map: PathMap) -> Self {
+        self.map = Some(map);
+        self
+    }
+
+    /// Serve over https using the acceptor
+    pub fn tls(mut self, acceptor: TlsAcceptor) -> Self {
+        self.acceptor = Some(acceptor);
+        self
+    }
+
+    /// Build server. Config is validated with [`DirectShareConfig::validated`]
+    pub fn build(self) -> DirectShare {
+        let map = self.map.unwrap_or_else(|| {
+            PathMap::new(self.config.key_length)
+                .with_style(self.config.key_style)
+                .with_alphabet(self.config.key_alphabet)
+                .with_case_insensitive(self.config.case_insensitive_keys)
+        });
+
+        DirectShare {
+            config: Arc::new(self.config.validated()),
+            map: Arc::new(RwLock::new(map)),
+            acceptor: self.acceptor,
+            activity: Arc::new(Activity::new()),
+            metrics: Arc::new(Metrics::new()),
+            pause: Arc::new(Pause::new()),
+            digests: Arc::new(DigestCache::new()),
+        }
+    }
+}
+
+#[derive(Clone)]
+/// File sharing server
+pub struct DirectShare {
+    config: Arc<DirectShareConfig>,
+    map: Arc<RwLock<PathMap>>,
+    acceptor: Option<TlsAcceptor>,
+    activity: Arc<Activity>,
+    metrics: Arc<Metrics>,
+    pause: Arc<Pause>,
+    digests: Arc<DigestCache>,
+}
+
+impl DirectShare {
+    pub fn builder(config: DirectShareConfig) -> DirectShareBuilder {
+        DirectShareBuilder::new(config)
+    }
+
+    pub const fn config(&self) -> &Arc<DirectShareConfig> {
+        &self.config
+    }
+
+    /// Links served by the server. Links can be changed while running.
+    pub const fn map(&self) -> &Arc<RwLock<PathMap>> {
+        &self.map
+    }
+
+    /// Url scheme of served links
+    pub const fn scheme(&self) -> &'static str {
+        if self.acceptor.is_some() {
+            "https"
+        } else {
+            "http"
+        }
+    }
+
+    /// Paused state. File requests are answered with 503 while paused
+    pub const fn pause(&self) -> &Arc<Pause> {
+        &self.pause
+    }
+
+    /// Get shared path of `key`. Returns `None` if the target is not a single path
+    pub fn get(&self, key: &str) -> Option<PathBuf> {
+        self.map.read().unwrap().get(key).cloned()
+    }
+
+    /// Remove link of `key` while running and return its target
+    pub fn unregister(&self, key: &str) -> Option<Target> {
+        self.map.write().unwrap().unregister(key)
+    }
+
+    /// Register path with default link options in config and return its key
+    pub fn register(&self, path: PathBuf) -> String {
+        let digest = self.content_digest(&path);
+        self.map
+            .write()
+            .unwrap()
+            .register_with_digest(path, digest, &link_options(&self.config))
+    }
+
+    /// Register existing path with default link options in config and return information of the link
+    pub fn register_info(&self, path: &Path) -> io::Result<Registration> {
+        let digest = self.content_digest(path);
+        self.map.write().unwrap().register_info_with_digest(
+            path,
+            digest,
+            &link_options(&self.config),
+        )
+    }
+
+    /// Sha256 of file content if keys are derived from it.
+    /// Computed before locking the map so lookups are not blocked while reading the file
+    fn content_digest(&self, path: &Path) -> Option<[u8; 32]> {
+        if !self.map.read().unwrap().hashes_content() || !path.is_file() {
+            return None;
+        }
+
+        // Let other tasks move off the worker while reading
+        let res = match Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                block_in_place(|| map::file_digest(path))
+            }
+            _ => map::file_digest(path),
+        };
+
+        res.inspect_err(|err| {
+            log::warn!(
+                "cannot hash content, using path instead path: {} err: {err}",
+                path.display()
+            )
+        })
+        .ok()
+    }
+
+    /// Register existing path under custom `key` with default link options in config
+    pub fn register_with_key(&self, key: String, path: &Path) -> Result<Registration, KeyError> {
+        let registration = Registration::new(key, path)?;
+        self.map.write().unwrap().register_with_key_options(
+            registration.key.clone(),
+            registration.path.clone(),
+            &link_options(&self.config),
+        )?;
+
+        Ok(registration)
+    }
+
+    /// Register in memory content downloaded as file named `name` and return its key
+    pub fn register_bytes(&self, name: String, data: impl Into<Bytes>) -> String {
+        self.map
+            .write()
+            .unwrap()
+            .register_bytes(name, data.into(), &link_options(&self.config))
+    }
+
+    /// Register paths served together as one archive and return its key
+    pub fn register_bundle(&self, paths: Vec<PathBuf>) -> String {
+        self.map
+            .write()
+            .unwrap()
+            .register_bundle(paths, &link_options(&self.config))
+    }
+
+    /// Bind `addr` and serve until the listener fails
+    pub async fn run(self, addr: SocketAddr) -> Result<Never, anyhow::Error> {
+        self.serve(bind(addr)?).await
+    }
+
+    /// Serve connections accepted from `listener`
+    pub async fn serve(self, listener: impl Into<Listener>) -> Result<Never, anyhow::Error> {
+        spawn(sweep_stale(self.map.clone()));
+
+        match listener.into() {
+            Listener::Tcp(listener) => server(listener, self).await,
+
+            #[cfg(unix)]
+            Listener::Unix(listener) => server(listener, self).await,
+        }
+    }
+
+    /// Serve prometheus metrics at `/metrics` on `listener`. Requests are not authenticated.
+    pub async fn serve_metrics(self, listener: TcpListener) -> Result<Never, anyhow::Error> {
+        loop {
+            let (stream, addr) = listener.accept().await?;
+
+            spawn({
+                let share = self.clone();
+
+                async move {
+                    if let Err(err) = http1::Builder::new()
+                        .serve_connection(
+                            TokioIo::new(stream),
+                            service_fn(|req| {
+                                let res = share.metrics_page(&req);
+                                async move { Ok::<_, Infallible>(res) }
+                            }),
+                        )
+                        .await
+                    {
+                        log::warn!("could not deliver metrics addr: {addr} err: {err}");
+                    }
+                }
+            });
+        }
+    }
+
+    fn metrics_page<B>(&self, req: &Request<B>) -> Response<BoxBody<Bytes, io::Error>> {
+        if req.method() != Method::GET || req.uri().path() != "/metrics" {
+            let mut res = Response::new(Empty::<Bytes>::new().map_err(|_| unreachable!()).boxed());
+            *res.status_mut() = StatusCode::NOT_FOUND;
+            return res;
+        }
+
+        let links = self.map.read().unwrap().links().count();
+        let text = self.metrics.render(self.activity.active(), links);
+
+        let mut res = Response::new(
+            Full::new(Bytes::from(text))
+                .map_err(|_| unreachable!())
+                .boxed(),
+        );
+        res.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/plain; version=0.0.4; charset=utf-8"),
+        );
+        res
+    }
+
+    /// Wait until no connection was open for `duration`
+    pub async fn wait_idle(&self, duration: Duration) {
+        self.activity.wait_idle(duration).await
+    }
+
+    /// Wait until every link is exhausted or expired and remaining connections are closed
+    pub async fn wait_done(&self) {
+        const INTERVAL: Duration = Duration::from_secs(1);
+
+        while !self.map.read().unwrap().is_done() {
+            sleep(INTERVAL).await;
+        }
+
+        self.activity.wait_idle(Duration::ZERO).await
+    }
+
+    /// Serve single plain http connection from `addr` until it closes.
+    /// Rate limits and access log are not applied.
+    pub async fn serve_io<I: AsyncRead + AsyncWrite + Unpin + 'static>(
+        &self,
+        stream: I,
+        addr: SocketAddr,
+    ) {
+        serve_connection(
+            stream,
+            addr,
+            &self.map,
+            &self.config,
+            &Throttle::default(),
+            &ServerState {
+                access_log: None,
+                request_limiter: None,
+                metrics: self.metrics.clone(),
+                archive_cache: None,
+                pause: self.pause.clone(),
+                digests: self.digests.clone(),
+            },
+            None,
+        )
+        .await
+    }
+}
+
+/// Default link options in config
+fn link_options(config: &DirectShareConfig) -> LinkOptions {
+    LinkOptions {
+        ttl: config.default_ttl_secs.map(Duration::from_secs),
+        download_limit: config.default_download_limit,
+        password: config.default_password.clone(),
+    }
+}
+
+/// Options for walking shared directories in config
+fn walk_options(config: &DirectShareConfig) -> WalkOptions {
+    WalkOptions {
+        follow_symlinks: config.follow_symlinks,
+        include_hidden: config.include_hidden,
+        exclude: ExcludeRules::new(config.exclude.iter().map(String::as_str)),
+    }
+}
+
+/// Periodically remove expired or exhausted links
+async fn sweep_stale(map: Arc<RwLock<PathMap>>) -> Never {
+    const INTERVAL: Duration = Duration::from_secs(60);
+
+    loop {
+        sleep(INTERVAL).await;
+
+        map.write().unwrap().remove_stale();
+    }
+}
+
+/// Periodically remove unused generated archives
+async fn sweep_archives(cache: Arc<ArchiveCache>) -> Never {
+    const INTERVAL: Duration = Duration::from_secs(60);
+
+    loop {
+        sleep(INTERVAL).await;
+
+        cache.remove_stale().await;
+    }
+}
+
+async fn sweep_clients(limiter: Arc<RequestLimiter>) -> Never {
+    loop {
+        sleep(limiter.window()).await;
+
+        limiter.remove_stale();
+    }
+}
+
+/// Bind tcp listener. Unspecified IPv6 address listens on IPv4 too if possible
+pub fn bind(addr: SocketAddr) -> io::Result<TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+
+    if let IpAddr::V6(ip) = addr.ip() {
+        if ip.is_unspecified() {
+            if let Err(err) = socket.set_only_v6(false) {
+                log::warn!("cannot enable dual-stack socket err: {err}");
+            }
+        }
+    }
+
+    #[cfg(not(windows))]
+    socket.set_reuse_address(true)?;
+
+    socket.bind(&addr.into())?;
+    socket.listen(1024)?;
+    socket.set_nonblocking(true)?;
+
+    TcpListener::from_std(socket.into())
+}
+
+#[derive(Clone)]
+/// Per server state shared by connections
+struct ServerState {
+    access_log: Option<AccessLog>,
+    request_limiter: Option<Arc<RequestLimiter>>,
+    metrics: Arc<Metrics>,
+    archive_cache: Option<Arc<ArchiveCache>>,
+    pause: Arc<Pause>,
+    digests: Arc<DigestCache>,
+}
+
+async fn server<A: Accept>(listener: A, share: DirectShare) -> Result<Never, anyhow::Error> {
+    let DirectShare {
+        config,
+        map,
+        acceptor,
+        activity,
+        metrics,
+        pause,
+        digests,
+    } = share;
+
+    let global_limiter = config
+        .global_rate_limit
+        .map(|rate| Arc::new(RateLimiter::new(rate)));
+
+    let access_log = config
+        .access_log
+        .clone()
+        .map(|path| AccessLog::open(path, config.access_log_rotate_size));
+
+    let request_limiter = config.rate_limit_requests.map(|limit| {
+        Arc::new(RequestLimiter::new(
+            limit,
+            Duration::from_secs(config.rate_limit_window_secs),
+        ))
+    });
+    if let Some(ref limiter) = request_limiter {
+        spawn(sweep_clients(limiter.clone()));
+    }
+
+    let archive_cache = config.archive_cache.then(|| {
+        let dir = config
+            .archive_cache_dir
+            .clone()
+            .unwrap_or_else(|| std::env::temp_dir().join(constants::CONFIG_DIR));
+
+        Arc::new(ArchiveCache::new(
+            dir,
+            Duration::from_secs(config.archive_cache_ttl_secs),
+        ))
+    });
+    if let Some(ref cache) = archive_cache {
+        spawn(sweep_archives(cache.clone()));
+    }
+
+    let state = ServerState {
+        access_log,
+        request_limiter,
+        metrics,
+        archive_cache,
+        pause,
+        digests,
+    };
+
+    let connections = Arc::new(Semaphore::new(config.max_connections.get()));
+
+    loop {
+        let permit = match connections.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                log::warn!(
+                    "connection limit reached, waiting for a connection to close max_connections: {}",
+                    config.max_connections
+                );
+                connections.clone().acquire_owned().await?
+            }
+        };
+
+        let (stream, addr) = listener.accept().await?;
+
+        log::trace!("{addr} connected");
+        let guard = activity.enter();
+
+        spawn({
+            let acceptor = acceptor.clone();
+            let map = map.clone();
+            let config = config.clone();
+            let throttle = Throttle::new(global_limiter.clone(), config.rate_limit);
+            let state = state.clone();
+
+            // Encrypted or rate limited connection cannot send files directly
+            let send_file = if config.zero_copy && acceptor.is_none() && !throttle.is_limited() {
+                A::send_file(&stream)
+            } else {
+                None
+            };
+
+            async move {
+                // Held until the connection closes
+                let _permit = permit;
+                let _guard = guard;
+
+                let stream = IdleTimeout::new(stream, timeout_secs(config.idle_timeout_secs));
+                match acceptor {
+                    Some(acceptor) => match acceptor.accept(stream).await {
+                        Ok(stream) => {
+                            serve_connection(stream, addr, &map, &config, &throttle, &state, None)
+                                .await
+                        }
+                        Err(err) => log::warn!("tls handshake failed addr: {addr} err: {err}"),
+                    },
+
+                    None => {
+                        serve_connection(stream, addr, &map, &config, &throttle, &state, send_file)
+                            .await
+                    }
+                }
+            }
+        });
+    }
+}
+
+async fn serve_connection<I: AsyncRead + AsyncWrite + Unpin + 'static>(
+    stream: I,
+    addr: SocketAddr,
+    map: &RwLock<PathMap>,
+    config: &DirectShareConfig,
+    throttle: &Throttle,
+    state: &ServerState,
+    send_file: Option<SendFileSlot>,
+) {
+    let mut builder = http1::Builder::new();
+    builder
+        .timer(TokioTimer::new())
+        .header_read_timeout(timeout_secs(config.header_timeout_secs));
+
+    // Zero copy body chunks must reach the stream without being flattened
+    if send_file.is_some() {
+        builder.writev(true);
+    }
+
+    if let Err(err) = builder
+        .serve_connection(
+            TokioIo::new(stream),
+            service_fn(|mut req| {
+                if let Some(ref slot) = send_file {
+                    req.extensions_mut().insert(slot.clone());
+                }
+
+                response(addr, map, config, throttle, state, req).map(Ok::<_, Infallible>)
+            }),
+        )
+        .await
+    {
+        log::warn!("could not deliver file from addr: {addr} err: {err}");
+    }
+}
+
+/// Timeout of the seconds, `None` if 0
+fn timeout_secs(secs: u64) -> Option<Duration> {
+    (secs != 0).then(|| Duration::from_secs(secs))
+}
+
+async fn response(
+    addr: SocketAddr,
+    map: &RwLock<PathMap>,
+    config: &DirectShareConfig,
+    throttle: &Throttle,
+    state: &ServerState,
+    req: Request<hyper::body::Incoming>,
+) -> Response<BoxBody<Bytes, io::Error>> {
+    let start = Instant::now();
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    let head = method == Method::HEAD;
+
+    // Headers are ignored unless trusted since any client can set them
+    let addr = if config.trust_proxy {
+        forwarded::client_ip(req.headers()).map_or(addr, |ip| SocketAddr::new(ip, addr.port()))
+    } else {
+        addr
+    };
+
+    let mut res = match state.request_limiter.as_deref() {
+        Some(limiter) if !limiter.check(addr.ip()) => {
+            log::warn!("too many requests addr: {addr}");
+            too_many_requests_page(limiter.window())
+        }
+
+        _ => handle(addr, map, config, throttle, state, req).await,
+    };
+
+    if let Some(ref origin) = config.cors_allow_origin {
+        if let Ok(origin) = HeaderValue::from_str(origin) {
+            res.headers_mut()
+                .insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+        }
+    }
+
+    // Zero copy responses are counted once they are sent, since their frames are placeholders
+    let zero_copy = if head {
+        None
+    } else {
+        res.extensions().get::<zero_copy::Sent>().cloned()
+    };
+
+    if let (Some(access_log), None) = (&state.access_log, &zero_copy) {
+        let bytes = res
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+            .or_else(|| res.body().size_hint().exact());
+
+        access_log.record(
+            addr,
+            &method,
+            res.extensions()
+                .get::<ResolvedPath>()
+                .map(|path| path.0.as_path()),
+            res.status(),
+            if head { Some(0) } else { bytes },
+        );
+    }
+
+    let status = res.status();
+    let kind = match res.extensions().get::<ResponseKind>() {
+        Some(kind) => kind.as_str(),
+        None if status == StatusCode::NOT_FOUND => "404",
+        None => "page",
+    };
+    let elapsed = start.elapsed();
+    if status.is_client_error() || status.is_server_error() {
+        log::warn!(
+            "method: {method} path: {path} status: {status} kind: {kind} elapsed: {elapsed:?} addr: {addr}"
+        );
+    } else {
+        log::info!(
+            "method: {method} path: {path} status: {status} kind: {kind} elapsed: {elapsed:?} addr: {addr}"
+        );
+    }
+
+    state.metrics.record(status);
+
+    // HEAD response contains headers only
+    if head {
+        res.map(|_| Empty::<Bytes>::new().map_err(|_| unreachable!()).boxed())
+    } else if let Some(sent) = zero_copy {
+        let metrics = state.metrics.clone();
+        let access_log = state.access_log.clone();
+        let path = res.extensions().get::<ResolvedPath>().cloned();
+
+        res.map(|body| {
+            EndBody::new(body, move || {
+                metrics.add_bytes(sent.get());
+
+                if let Some(access_log) = access_log {
+                    access_log.record(
+                        addr,
+                        &method,
+                        path.as_ref().map(|path| path.0.as_path()),
+                        status,
+                        Some(sent.get()),
+                    );
+                }
+            })
+            .boxed()
+        })
+    } else {
+        let metrics = state.metrics.clone();
+        res.map(|body| {
+            body.map_frame(move |frame| {
+                if let Some(data) = frame.data_ref() {
+                    metrics.add_bytes(data.len() as u64);
+                }
+
+                frame
+            })
+            .boxed()
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// What was served by the response, stored in response extensions
+enum ResponseKind {
+    File,
+    Directory,
+    Listing,
+    Bundle,
+    Bytes,
+}
+
+impl ResponseKind {
+    const fn as_str(self) -> &'static str {
+        match self {
+            ResponseKind::File => "file",
+            ResponseKind::Directory => "dir",
+            ResponseKind::Listing => "listing",
+            ResponseKind::Bundle => "bundle",
+            ResponseKind::Bytes => "bytes",
+        }
+    }
+}
+
+async fn handle(
+    addr: SocketAddr,
+    map: &RwLock<PathMap>,
+    config: &DirectShareConfig,
+    throttle: &Throttle,
+    state: &ServerState,
+    mut req: Request<hyper::body::Incoming>,
+) -> Response<BoxBody<Bytes, io::Error>> {
+    let archive_cache = state.archive_cache.as_deref();
+    let method = req.method().clone();
+    let uri = req.uri().clone();
+    let path = {
+        let mut chars = uri.path().chars();
+        chars.next();
+
+        chars.as_str()
+    };
+
+    let path = match config.path_prefix {
+        Some(ref prefix) => match path
+            .strip_prefix(prefix.as_str())
+            .and_then(|path| path.strip_prefix('/'))
+        {
+            Some(path) => path,
+            None => return not_found_page(config).await,
+        },
+
+        None => path,
+    };
+
+    // Admin api is guarded by its own token
+    if let Some(ref token) = config.admin_token {
+        if let Some(route) = path
+            .strip_prefix(constants::ADMIN_PATH)
+            .and_then(|route| route.strip_prefix('/'))
+        {
+            let route = route.to_string();
+            return admin::handle(&route, token, map, &state.pause, req).await;
+        }
+    }
+
+    // Admin api stays available to resume
+    if state.pause.is_paused() {
+        return service_unavailable_page(config).await;
+    }
+
+    // Preflight requests are sent without credentials
+    if Method::OPTIONS == method {
+        let allow = if config.allow_upload && path == constants::UPLOAD_PATH {
+            "POST, OPTIONS"
+        } else {
+            "GET, HEAD, OPTIONS"
+        };
+
+        return options_page(allow, &req);
+    }
+
+    // Browsers request the icon on their own, answer without noisy 404
+    if path == constants::FAVICON_PATH
+        && (Method::GET == method || Method::HEAD == method)
+        && map.read().unwrap().get_target(path).is_none()
+    {
+        return favicon_page(config.favicon);
+    }
+
+    if let Some(ref credentials) = config.auth {
+        let authorized = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| credentials.verify(value));
+
+        if !authorized {
+            log::warn!("unauthorized request path: {path} addr: {addr}");
+            return unauthorized_page();
+        }
+    }
+
+    if config.allow_upload && path == constants::UPLOAD_PATH {
+        return upload::handle(addr, config, req).await;
+    }
+
+    if Method::GET != method && Method::HEAD != method {
+        return not_found_page(config).await;
+    }
+
+    if path.is_empty() {
+        return landing_page(map, config, &req);
+    }
+
+    let (key, sub_path) = match path.split_once('/') {
+        Some((key, sub_path)) => (key, Some(sub_path)),
+        None => (path, None),
+    };
+
+    // Checksum sidecar of shared file, unless the key itself is taken
+    let (key, checksum) = match key.strip_suffix(constants::CHECKSUM_SUFFIX) {
+        Some(base)
+            if config.checksums
+                && sub_path.is_none()
+                && map.read().unwrap().get_target(key).is_none() =>
+        {
+            (base, true)
+        }
+
+        _ => (key, false),
+    };
+
+    let Some(target) = map.read().unwrap().get_target(key).cloned() else {
+        return not_found_page(config).await;
+    };
+
+    if map.read().unwrap().is_password_protected(key) {
+        let Some(password) = link_password(&req) else {
+            return unauthorized_page();
+        };
+
+        if !map.read().unwrap().verify_password(key, &password) {
+            log::warn!("wrong link password key: {key} addr: {addr}");
+            return forbidden_page();
+        }
+    }
+
+    // Every download of limited link must be counted, so it is always sent whole
+    if map.read().unwrap().is_download_limited(key) {
+        req.headers_mut().remove(header::RANGE);
+    }
+
+    if checksum {
+        return match target {
+            Target::Path(path) => serve_checksum(&path, config, &state.digests).await,
+            _ => not_found_page(config).await,
+        };
+    }
+
+    let root = match target {
+        Target::Path(root) => root,
+
+        Target::Bundle(paths) => {
+            if sub_path.is_some() {
+                return not_found_page(config).await;
+            }
+
+            log::info!("serving bundle of {} paths addr: {addr}", paths.len());
+
+            let get = Method::GET == method;
+            let key = key.to_string();
+            let mut res = serve_archive(
+                ArchiveSource::bundle(&paths),
+                constants::BUNDLE_NAME,
+                config,
+                throttle,
+                archive_cache,
+                &state.digests,
+                req,
+            )
+            .await;
+            res.extensions_mut().insert(ResponseKind::Bundle);
+
+            if !get {
+                return res;
+            }
+
+            if !consume_download(map, &key, &res) {
+                return not_found_page(config).await;
+            }
+
+            return log_transfer(res, key, addr);
+        }
+
+        Target::Bytes { name, data } => {
+            if sub_path.is_some() {
+                return not_found_page(config).await;
+            }
+
+            log::info!("serving in memory content: {name} addr: {addr}");
+
+            let mut res = serve_bytes(&name, data, config, throttle);
+            res.extensions_mut().insert(ResponseKind::Bytes);
+
+            if Method::GET != method {
+                return res;
+            }
+
+            if !consume_download(map, key, &res) {
+                return not_found_page(config).await;
+            }
+
+            return log_transfer(res, key.to_string(), addr);
+        }
+    };
+
+    let file_path = match sub_path {
+        // Files of static site are served under the directory
+        Some(sub_path) if config.directory_mode == DirectoryMode::Listing || config.serve_index => {
+            match listing::resolve_sub_path(&root, sub_path) {
+                Some(file_path)
+                    if config.follow_symlinks || listing::is_contained(&root, &file_path).await =>
+                {
+                    file_path
+                }
+
+                Some(file_path) => {
+                    log::warn!(
+                        "rejected path escaping shared directory path: {} addr: {addr}",
+                        file_path.display()
+                    );
+                    return not_found_page(config).await;
+                }
+
+                None => return not_found_page(config).await,
+            }
+        }
+
+        Some(_) => return not_found_page(config).await,
+
+        None => root.clone(),
+    };
+
+    let meta = match fs::metadata(&file_path).await {
+        Ok(meta) => meta,
+        Err(err) => {
+            log::error!("cannot stat {} err: {err}", file_path.display());
+
+            return not_found_page(config).await;
+        }
+    };
+
+    // Serve index page instead if the directory is a static site
+    let index_meta = if meta.is_dir() && config.serve_index {
+        fs::metadata(file_path.join(constants::INDEX_FILE))
+            .await
+            .ok()
+            .filter(|index_meta| index_meta.is_file())
+    } else {
+        None
+    };
+
+    let site = index_meta.is_some();
+    let (file_path, meta) = match index_meta {
+        Some(index_meta) => {
+            // Relative links in the page need trailing slash
+            if !path.ends_with('/') {
+                let path = config.prefixed(path);
+                let location = match req.uri().query() {
+                    Some(query) => format!("/{path}/?{query}"),
+                    None => format!("/{path}/"),
+                };
+
+                return redirect_page(&location);
+            }
+
+            (file_path.join(constants::INDEX_FILE), index_meta)
+        }
+
+        None => (file_path, meta),
+    };
+
+    let file_name = file_path
+        .file_name()
+        .map(|os_str| os_str.to_string_lossy().to_string())
+        .unwrap_or(constants::FALLBACK_FILENAME.into());
+
+    let listing = !meta.is_file() && config.directory_mode == DirectoryMode::Listing;
+
+    // Browsers see the archive before starting the download, which is not counted
+    if config.archive_download_page
+        && Method::GET == method
+        && !meta.is_file()
+        && !listing
+        && wants_download_page(&req)
+    {
+        return archive_download_page(
+            &ArchiveSource::Directory(file_path),
+            &file_name,
+            config,
+            &req,
+        )
+        .await;
+    }
+
+    if listing {
+        log::info!("serving listing: {} addr: {addr}", file_path.display());
+
+        let relative_path = file_path.strip_prefix(&root).unwrap_or(Path::new(""));
+        let mut res = serve_listing(&file_path, key, relative_path, config).await;
+        res.extensions_mut().insert(ResolvedPath(file_path));
+        res.extensions_mut().insert(ResponseKind::Listing);
+
+        return res;
+    }
+
+    let get = Method::GET == method;
+    let key = key.to_string();
+
+    let kind = if meta.is_file() {
+        ResponseKind::File
+    } else {
+        ResponseKind::Directory
+    };
+
+    let mut res = if meta.is_file() {
+        log::info!("serving file: {} addr: {addr}", file_path.display());
+        serve_file(
+            ServedFile {
+                path: &file_path,
+                name: &file_name,
+                meta,
+            },
+            config.force_download && !site,
+            config,
+            throttle,
+            &state.digests,
+            req,
+        )
+        .await
+    } else {
+        log::info!("serving directory: {} addr: {addr}", file_path.display());
+        serve_archive(
+            ArchiveSource::Directory(file_path.clone()),
+            &file_name,
+            config,
+            throttle,
+            archive_cache,
+            &state.digests,
+            req,
+        )
+        .await
+    };
+    res.extensions_mut().insert(ResolvedPath(file_path));
+    res.extensions_mut().insert(kind);
+
+    // Listing pages and HEAD requests are not counted as download
+    if !get {
+        return res;
+    }
+
+    if !consume_download(map, &key, &res) {
+        return not_found_page(config).await;
+    }
+
+    log_transfer(res, key, addr)
+}
+
+/// Count download of `key` if the response sends content,
+/// so HEAD and not modified responses are free.
+/// Returns `false` if the link has no download left
+fn consume_download<B>(map: &RwLock<PathMap>, key: &str, res: &Response<B>) -> bool {
+    let sends_content = matches!(res.status(), StatusCode::OK | StatusCode::PARTIAL_CONTENT);
+
+    !sends_content || map.read().unwrap().consume(key)
+}
+
+/// Add `Content-Digest` header of the file.
+/// Digest covers whole content, so partial and compressed responses are left out
+async fn insert_digest(
+    res: &mut Response<BoxBody<Bytes, io::Error>>,
+    path: &Path,
+    meta: &Metadata,
+    digests: &DigestCache,
+) {
+    if res.status() != StatusCode::OK || res.headers().contains_key(header::CONTENT_ENCODING) {
+        return;
+    }
+
+    match digests.get(path, meta).await {
+        Ok(digest) => {
+            res.headers_mut().insert(
+                HeaderName::from_static("content-digest"),
+                digest::content_digest(&digest).parse().unwrap(),
+            );
+        }
+
+        Err(err) => {
+            log::warn!("cannot hash file path: {} err: {err}", path.display());
+        }
+    }
+}
+
+/// Log stats of successful download when the transfer ends
+fn log_transfer(
+    res: Response<BoxBody<Bytes, io::Error>>,
+    key: String,
+    addr: SocketAddr,
+) -> Response<BoxBody<Bytes, io::Error>> {
+    if !res.status().is_success() {
+        return res;
+    }
+
+    let length = res
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok());
+
+    let sent = res.extensions().get::<zero_copy::Sent>().cloned();
+    res.map(|body| {
+        let body = LoggedBody::new(body, length, key, addr);
+
+        match sent {
+            Some(sent) => body.with_zero_copy(sent).boxed(),
+            None => body.boxed(),
+        }
+    })
+}
+
+/// File served with [`serve_file`]
+struct ServedFile<'a> {
+    path: &'a Path,
+
+    /// Name of the file sent to recipient
+    name: &'a str,
+
+    meta: Metadata,
+}
+
+async fn serve_file(
+    file: ServedFile<'_>,
+    force_download: bool,
+    config: &DirectShareConfig,
+    throttle: &Throttle,
+    digests: &DigestCache,
+    req: Request<hyper::body::Incoming>,
+) -> Response<BoxBody<Bytes, io::Error>> {
+    let ServedFile {
+        path,
+        name: file_name,
+        meta,
+    } = file;
+    let len = meta.len();
+
+    let range = match req.headers().get(header::RANGE).map(|value| {
+        value
+            .to_str()
+            .map_err(|_| range::RangeError::Malformed)
+            .and_then(|value| parse_range(value, len))
+    }) {
+        Some(Ok(range)) => range,
+        Some(Err(err)) => {
+            log::warn!("invalid range request path: {} err: {err}", path.display());
+            return range_not_satisfiable_page(len);
+        }
+        None => None,
+    };
+
+    let modified = meta.modified().ok();
+
+    let media_type = mime_guess::from_path(path).first();
+
+    let encoding = if range.is_none()
+        && config.compression
+        && media_type.as_ref().is_some_and(media::is_compressible)
+    {
+        req.headers()
+            .get(header::ACCEPT_ENCODING)
+            .and_then(|value| value.to_str().ok())
+            .and_then(media::preferred_encoding)
+    } else {
+        None
+    };
+
+    let etag = if config.strong_etags {
+        match digests.get(path, &meta).await {
+            Ok(digest) => cache::strong_etag(&digest),
+            Err(err) => {
+                log::warn!("cannot hash file path: {} err: {err}", path.display());
+                cache::weak_etag(len, modified)
+            }
+        }
+    } else {
+        cache::weak_etag(len, modified)
+    };
+
+    // Encoded representation has its own tag
+    let etag = match encoding {
+        Some(encoding) => cache::encoded_etag(&etag, encoding.as_str()),
+        None => etag,
+    };
+
+    // If-Modified-Since is ignored if If-None-Match is present
+    let not_modified = match req.headers().get(header::IF_NONE_MATCH) {
+        Some(value) => value
+            .to_str()
+            .is_ok_and(|value| cache::etag_matches(value, &etag)),
+
+        None => modified.is_some_and(|modified| {
+            req.headers()
+                .get(header::IF_MODIFIED_SINCE)
+                .and_then(|value| value.to_str().ok())
+                .is_some_and(|value| !cache::is_modified_since(value, modified))
+        }),
+    };
+
+    if not_modified {
+        return not_modified_page(&etag, modified);
+    }
+
+    let mut file = match File::open(path).await {
+        Ok(file) => file,
+        Err(err) => {
+            log::error!("cannot open file path: {} err: {err}", path.display());
+            return not_found_page(config).await;
+        }
+    };
+
+    let mut res = match (range, encoding) {
+        (Some(range), _) => {
+            if let Err(err) = file.seek(SeekFrom::Start(range.start)).await {
+                log::error!("cannot seek file path: {} err: {err}", path.display());
+                return not_found_page(config).await;
+            }
+
+            partial_file_response(file, range, len, config.file_buffer_size, throttle)
+        }
+
+        // Compressed size is unknown, send without Content-Length
+        (None, Some(encoding)) => {
+            let reader = BufReader::with_capacity(config.file_buffer_size, file);
+            let mut res = Response::new(match encoding {
+                Encoding::Brotli => stream_body(
+                    BrotliEncoder::new(reader),
+                    config.file_buffer_size,
+                    throttle,
+                ),
+                Encoding::Gzip => {
+                    stream_body(GzipEncoder::new(reader), config.file_buffer_size, throttle)
+                }
+            });
+
+            res.headers_mut().insert(
+                header::CONTENT_ENCODING,
+                HeaderValue::from_static(encoding.as_str()),
+            );
+
+            res
+        }
+
+        // Growing file is sent until its current end with chunked encoding
+        (None, None) if config.live_files => {
+            Response::new(stream_body(file, config.file_buffer_size, throttle))
+        }
+
+        (None, None) => {
+            let mut res = match req.extensions().get::<SendFileSlot>() {
+                Some(slot) => match zero_copy::body(file, len, slot.clone()).await {
+                    Ok((body, sent)) => {
+                        let mut res = Response::new(body);
+                        res.extensions_mut().insert(sent);
+
+                        res
+                    }
+
+                    Err(err) => {
+                        log::error!("cannot read file path: {} err: {err}", path.display());
+                        return not_found_page(config).await;
+                    }
+                },
+
+                None => Response::new(stream_body(
+                    SizedReader::new(file, len, path.to_path_buf()),
+                    config.file_buffer_size,
+                    throttle,
+                )),
+            };
+
+            res.headers_mut()
+                .insert(header::CONTENT_LENGTH, HeaderValue::from(len));
+
+            res
+        }
+    };
+
+    let inline = !force_download
+        && disposition::is_inline(&config.disposition, file_name, media_type.as_ref());
+
+    let headers = res.headers_mut();
+    headers.insert(header::ACCEPT_RANGES, "bytes".parse().unwrap());
+    headers.insert(header::ETAG, etag.parse().unwrap());
+    if let Some(modified) = modified {
+        headers.insert(
+            header::LAST_MODIFIED,
+            cache::last_modified(modified).parse().unwrap(),
+        );
+    }
+    if config.compression {
+        headers.insert(header::VARY, "accept-encoding".parse().unwrap());
+    }
+    headers.insert(
+        header::CONTENT_TYPE,
+        media_type
+            .unwrap_or(mime_guess::mime::APPLICATION_OCTET_STREAM)
+            .as_ref()
+            .parse()
+            .unwrap(),
+    );
+    match disposition::content_disposition(inline, file_name) {
+        Ok(value) => {
+            headers.insert(header::CONTENT_DISPOSITION, value);
+        }
+        Err(err) => {
+            log::warn!(
+                "cannot create content disposition path: {} err: {err}",
+                path.display()
+            );
+        }
+    }
+
+    if config.send_digest && !config.live_files {
+        insert_digest(&mut res, path, &meta, digests).await;
+    }
+
+    res
+}
+
+/// Serve one line sha256sum format checksum of the file, checked with `sha256sum -c`
+async fn serve_checksum(
+    path: &Path,
+    config: &DirectShareConfig,
+    digests: &DigestCache,
+) -> Response<BoxBody<Bytes, io::Error>> {
+    let meta = match fs::metadata(path).await {
+        Ok(meta) if meta.is_file() => meta,
+        Ok(_) => return not_found_page(config).await,
+        Err(err) => {
+            log::error!("cannot stat {} err: {err}", path.display());
+            return not_found_page(config).await;
+        }
+    };
+
+    let digest = match digests.get(path, &meta).await {
+        Ok(digest) => digest,
+        Err(err) => {
+            log::warn!("cannot hash file path: {} err: {err}", path.display());
+            return not_found_page(config).await;
+        }
+    };
+
+    // Recipients check the file saved under the name sent in Content-Disposition
+    let file_name = util::sanitize_filename(
+        &path
+            .file_name()
+            .map(|os_str| os_str.to_string_lossy().to_string())
+            .unwrap_or(constants::FALLBACK_FILENAME.into()),
+    );
+    let text = format!("{}  {file_name}\n", auth::to_hex(&digest));
+
+    let mut res = Response::new(
+        Full::new(Bytes::from(text))
+            .map_err(|_| unreachable!())
+            .boxed(),
+    );
+    let headers = res.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/plain; charset=utf-8"),
+    );
+    match disposition::content_disposition(
+        false,
+        &format!("{file_name}{}", constants::CHECKSUM_SUFFIX),
+    ) {
+        Ok(value) => {
+            headers.insert(header::CONTENT_DISPOSITION, value);
+        }
+        Err(err) => {
+            log::warn!(
+                "cannot create content disposition path: {} err: {err}",
+                path.display()
+            );
+        }
+    }
+
+    res
+}
+
+fn serve_bytes(
+    name: &str,
+    data: Bytes,
+    config: &DirectShareConfig,
+    throttle: &Throttle,
+) -> Response<BoxBody<Bytes, io::Error>> {
+    let media_type = mime_guess::from_path(name).first();
+    let inline = !config.force_download
+        && disposition::is_inline(&config.disposition, name, media_type.as_ref());
+    let len = data.len() as u64;
+
+    let mut res = Response::new(stream_body(
+        Cursor::new(data),
+        config.file_buffer_size,
+        throttle,
+    ));
+
+    let headers = res.headers_mut();
+    headers.insert(header::CONTENT_LENGTH, HeaderValue::from(len));
+    headers.insert(
+        header::CONTENT_TYPE,
+        media_type
+            .unwrap_or(mime_guess::mime::APPLICATION_OCTET_STREAM)
+            .as_ref()
+            .parse()
+            .unwrap(),
+    );
+    match disposition::content_disposition(inline, name) {
+        Ok(value) => {
+            headers.insert(header::CONTENT_DISPOSITION, value);
+        }
+        Err(err) => {
+            log::warn!("cannot create content disposition name: {name} err: {err}");
+        }
+    }
+
+    res
+}
+
+fn partial_file_response(
+    file: File,
+    range: ByteRange,
+    total: u64,
+    capacity: usize,
+    throttle: &Throttle,
+) -> Response<BoxBody<Bytes, io::Error>> {
+    let mut res = Response::new(stream_body(
+        file.take(range.content_length()),
+        capacity,
+        throttle,
+    ));
+    *res.status_mut() = StatusCode::PARTIAL_CONTENT;
+
+    let headers = res.headers_mut();
+    headers.insert(
+        header::CONTENT_LENGTH,
+        HeaderValue::from(range.content_length()),
+    );
+    headers.insert(
+        header::CONTENT_RANGE,
+        range.content_range(total).parse().unwrap(),
+    );
+
+    res
+}
+
+async fn serve_archive(
+    source: ArchiveSource,
+    name: &str,
+    config: &DirectShareConfig,
+    throttle: &Throttle,
+    archive_cache: Option<&ArchiveCache>,
+    digests: &DigestCache,
+    req: Request<hyper::body::Incoming>,
+) -> Response<BoxBody<Bytes, io::Error>> {
+    let format = config.archive_format;
+    let options = walk_options(config);
+    let archive_name = format!("{name}.{}", format.extension());
+
+    // Generated archive is a regular file supporting any range
+    if let Some(cache) = archive_cache {
+        let cached = match cache
+            .get(
+                &source,
+                format,
+                config.archive_gzip_level,
+                &options,
+                config.max_archive_size,
+            )
+            .await
+        {
+            Ok(path) => fs::metadata(&path).await.map(|meta| (path, meta)),
+            Err(err) => Err(err),
+        };
+
+        match cached {
+            Ok((path, meta)) => {
+                return serve_file(
+                    ServedFile {
+                        path: &path,
+                        name: &archive_name,
+                        meta,
+                    },
+                    true,
+                    config,
+                    throttle,
+                    digests,
+                    req,
+                )
+                .await
+            }
+            Err(err) if err.kind() == io::ErrorKind::FileTooLarge => return forbidden_page(),
+            Err(err) => {
+                log::warn!(
+                    "cannot generate archive, streaming instead archive: {archive_name} err: {err}"
+                )
+            }
+        }
+    }
+
+    // Archive is generated again so only resuming from an offset is supported.
+    // Resumed download is correct only if the contents did not change.
+    let offset = req
+        .headers()
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(range::parse_resume);
+
+    // Resumed tar needs its size for Content-Range
+    let total = match format {
+        ArchiveFormat::Tar if config.compute_archive_size || offset.is_some() => {
+            match source.tar_size(&options).await {
+                Ok(total) => Some(total),
+                Err(err) => {
+                    log::warn!("cannot compute archive size archive: {archive_name} err: {err}");
+                    None
+                }
+            }
+        }
+
+        _ => None,
+    };
+
+    if let (Some(total), Some(limit)) = (total, config.max_archive_size) {
+        if total > limit {
+            log::warn!(
+                "archive exceeds max_archive_size archive: {archive_name} size: {total} limit: {limit}"
+            );
+            return forbidden_page();
+        }
+    }
+
+    // Size of zip and compressed tar is unknown before generation, so they are sent whole
+    let offset = match (offset, total) {
+        (Some(offset), Some(total)) if offset >= total => {
+            return range_not_satisfiable_page(total);
+        }
+
+        (Some(offset), None) => {
+            log::debug!(
+                "ignoring range of unsized archive archive: {archive_name} offset: {offset}"
+            );
+            None
+        }
+
+        (offset, _) => offset,
+    };
+
+    // Skip archive generation for HEAD request
+    let body = if req.method() != Method::HEAD {
+        let offset = offset.unwrap_or(0);
+        let reader = source.spawn(
+            format,
+            config.archive_gzip_level,
+            options,
+            offset,
+            config.tar_buffer_size,
+            archive_name.clone(),
+        );
+
+        // Dropping the reader on overflow aborts generation
+        match config.max_archive_size {
+            Some(limit) => stream_body(
+                SizeLimit::new(reader, limit.saturating_sub(offset), archive_name),
+                config.tar_buffer_size,
+                throttle,
+            ),
+            None => stream_body(reader, config.tar_buffer_size, throttle),
+        }
+    } else {
+        Empty::<Bytes>::new().map_err(|_| unreachable!()).boxed()
+    };
+
+    let mut res = Response::new(body);
+    match (offset, total) {
+        (Some(offset), Some(total)) => {
+            let range = ByteRange {
+                start: offset,
+                end: total - 1,
+            };
+
+            *res.status_mut() = StatusCode::PARTIAL_CONTENT;
+            res.headers_mut().insert(
+                header::CONTENT_RANGE,
+                HeaderValue::from_str(&range.content_range(total)).unwrap(),
+            );
+            res.headers_mut()
+                .insert(header::CONTENT_LENGTH, range.content_length().into());
+        }
+
+        (None, Some(total)) => {
+            res.headers_mut()
+                .insert(header::CONTENT_LENGTH, total.into());
+        }
+
+        _ => {}
+    }
+
+    let headers = res.headers_mut();
+    headers.insert(header::CONTENT_TYPE, format.content_type().parse().unwrap());
+    match disposition::content_disposition(false, &format!("{name}.{}", format.extension())) {
+        Ok(value) => {
+            headers.insert(header::CONTENT_DISPOSITION, value);
+        }
+        Err(err) => {
+            log::warn!("cannot create content disposition archive: {name} err: {err}");
+        }
+    }
+
+    res
+}
+
+/// Create body streaming from reader, paced by throttle
+fn stream_body<R: AsyncRead + Send + Sync + 'static>(
+    reader: R,
+    capacity: usize,
+    throttle: &Throttle,
+) -> BoxBody<Bytes, io::Error> {
+    if !throttle.is_limited() {
+        return StreamBody::new(ReaderStream::with_capacity(reader, capacity).map_ok(Frame::data))
+            .boxed();
+    }
+
+    let stream = ReaderStream::with_capacity(reader, throttle.chunk_size(capacity));
+    StreamBody::new(throttle.wrap(stream).map_ok(Frame::data)).boxed()
+}
+
+async fn serve_listing(
+    path: &Path,
+    key: &str,
+    relative_path: &Path,
+    config: &DirectShareConfig,
+) -> Response<BoxBody<Bytes, io::Error>> {
+    let encoded_path = listing::encode_path(relative_path);
+    let base = if encoded_path.is_empty() {
+        format!("/{}", config.prefixed(key))
+    } else {
+        format!("/{}/{encoded_path}", config.prefixed(key))
+    };
+
+    let root_name = path
+        .file_name()
+        .map(|os_str| os_str.to_string_lossy().to_string())
+        .unwrap_or(constants::FALLBACK_FILENAME.into());
+    let title = if relative_path.as_os_str().is_empty() {
+        root_name
+    } else {
+        relative_path.to_string_lossy().to_string()
+    };
+
+    let page =
+        match listing::render(path, &title, &base, !relative_path.as_os_str().is_empty()).await {
+            Ok(page) => page,
+            Err(err) => {
+                log::error!("cannot read directory path: {} err: {err}", path.display());
+                return not_found_page(config).await;
+            }
+        };
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+        .body(
+            Full::new(Bytes::from(page))
+                .map_err(|_| unreachable!())
+                .boxed(),
+        )
+        .unwrap()
+}
+
+async fn not_found_page(config: &DirectShareConfig) -> Response<BoxBody<Bytes, io::Error>> {
+    file_page(
+        StatusCode::NOT_FOUND,
+        "default_file",
+        config.default_file.as_deref(),
+    )
+    .await
+}
+
+async fn service_unavailable_page(
+    config: &DirectShareConfig,
+) -> Response<BoxBody<Bytes, io::Error>> {
+    file_page(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "maintenance_file",
+        config.maintenance_file.as_deref(),
+    )
+    .await
+}
+
+/// Page with content of configured `file`. Body is empty if not set or cannot be read
+async fn file_page(
+    status: StatusCode,
+    option: &str,
+    file: Option<&str>,
+) -> Response<BoxBody<Bytes, io::Error>> {
+    if let Some(file) = file {
+        match fs::read(file).await {
+            Ok(data) => {
+                return Response::builder()
+                    .status(status)
+                    .header(
+                        header::CONTENT_TYPE,
+                        mime_guess::from_path(file)
+                            .first_or(mime_guess::mime::TEXT_HTML)
+                            .as_ref(),
+                    )
+                    .body(
+                        Full::new(Bytes::from(data))
+                            .map_err(|_| unreachable!())
+                            .boxed(),
+                    )
+                    .unwrap();
+            }
+
+            Err(err) => {
+                log::warn!("cannot read {option}: {file} err: {err}");
+            }
+        }
+    }
+
+    Response::builder()
+        .status(status)
+        .body(Empty::<Bytes>::new().map_err(|_| unreachable!()).boxed())
+        .unwrap()
+}
+
+/// Landing page showing link count. Links are listed to admin if enabled
+fn landing_page<B>(
+    map: &RwLock<PathMap>,
+    config: &DirectShareConfig,
+    req: &Request<B>,
+) -> Response<BoxBody<Bytes, io::Error>> {
+    let show_links = config.landing_show_links
+        && config
+            .admin_token
+            .as_ref()
+            .is_some_and(|token| admin::is_authorized(req.headers(), token));
+
+    let map = map.read().unwrap();
+    let links = show_links.then(|| {
+        map.links()
+            .map(|(key, target)| (format!("/{}", config.prefixed(key)), target.to_string()))
+            .collect::<Vec<_>>()
+    });
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(
+            Full::new(Bytes::from(landing::render(
+                map.links().count(),
+                links.as_deref(),
+            )))
+            .map_err(|_| unreachable!())
+            .boxed(),
+        )
+        .unwrap()
+}
+
+/// Check if the request comes from browser not starting the download yet
+fn wants_download_page<B>(req: &Request<B>) -> bool {
+    let download = req.uri().query().is_some_and(|query| {
+        form_urlencoded::parse(query.as_bytes())
+            .any(|(name, _)| name == download_page::DOWNLOAD_PARAM)
+    });
+
+    !download
+        && req
+            .headers()
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(media::accepts_html)
+}
+
+async fn archive_download_page<B>(
+    source: &ArchiveSource,
+    name: &str,
+    config: &DirectShareConfig,
+    req: &Request<B>,
+) -> Response<BoxBody<Bytes, io::Error>> {
+    // Compressed archive is smaller, tar size is the upper bound
+    let size = match source.tar_size(&walk_options(config)).await {
+        Ok(size) => Some(size),
+        Err(err) => {
+            log::warn!("cannot estimate archive size archive: {name} err: {err}");
+            None
+        }
+    };
+
+    let page = download_page::render(
+        &format!("{name}.{}", config.archive_format.extension()),
+        size,
+        &download_page::download_href(req.uri().query()),
+    );
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(
+            Full::new(Bytes::from(page))
+                .map_err(|_| unreachable!())
+                .boxed(),
+        )
+        .unwrap()
+}
+
+/// Find link password from `pw` query or basic authorization
+fn link_password<B>(req: &Request<B>) -> Option<String> {
+    let query_password = req.uri().query().and_then(|query| {
+        form_urlencoded::parse(query.as_bytes())
+            .find(|(name, _)| name == "pw")
+            .map(|(_, value)| value.into_owned())
+    });
+
+    query_password.or_else(|| {
+        req.headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(auth::parse_basic)
+            .map(|(_, password)| password)
+    })
+}
+
+fn not_modified_page(
+    etag: &str,
+    modified: Option<SystemTime>,
+) -> Response<BoxBody<Bytes, io::Error>> {
+    let mut builder = Response::builder()
+        .status(StatusCode::NOT_MODIFIED)
+        .header(header::ETAG, etag);
+
+    if let Some(modified) = modified {
+        builder = builder.header(header::LAST_MODIFIED, cache::last_modified(modified));
+    }
+
+    builder
+        .body(Empty::<Bytes>::new().map_err(|_| unreachable!()).boxed())
+        .unwrap()
+}
+
+fn redirect_page(location: &str) -> Response<BoxBody<Bytes, io::Error>> {
+    Response::builder()
+        .status(StatusCode::MOVED_PERMANENTLY)
+        .header(header::LOCATION, location)
+        .body(Empty::<Bytes>::new().map_err(|_| unreachable!()).boxed())
+        .unwrap()
+}
+
+fn forbidden_page() -> Response<BoxBody<Bytes, io::Error>> {
+    Response::builder()
+        .status(StatusCode::FORBIDDEN)
+        .body(Empty::<Bytes>::new().map_err(|_| unreachable!()).boxed())
+        .unwrap()
+}
+
+fn options_page<B>(allow: &'static str, req: &Request<B>) -> Response<BoxBody<Bytes, io::Error>> {
+    let mut builder = Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .header(header::ALLOW, allow)
+        .header(header::ACCESS_CONTROL_ALLOW_METHODS, allow);
+
+    if let Some(headers) = req.headers().get(header::ACCESS_CONTROL_REQUEST_HEADERS) {
+        builder = builder.header(header::ACCESS_CONTROL_ALLOW_HEADERS, headers);
+    }
+
+    builder
+        .body(Empty::<Bytes>::new().map_err(|_| unreachable!()).boxed())
+        .unwrap()
+}
+
+fn favicon_page(favicon: Favicon) -> Response<BoxBody<Bytes, io::Error>> {
+    static ICON: &[u8] = include_bytes!("favicon.ico");
+
+    match favicon {
+        Favicon::Icon => Response::builder()
+            .header(header::CONTENT_TYPE, "image/x-icon")
+            .header(header::CACHE_CONTROL, "max-age=86400")
+            .body(
+                Full::new(Bytes::from_static(ICON))
+                    .map_err(|_| unreachable!())
+                    .boxed(),
+            )
+            .unwrap(),
+
+        Favicon::Empty => Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(Empty::<Bytes>::new().map_err(|_| unreachable!()).boxed())
+            .unwrap(),
+    }
+}
+
+fn unauthorized_page() -> Response<BoxBody<Bytes, io::Error>> {
+    Response::builder()
+        .status(StatusCode::UNAUTHORIZED)
+        .header(
+            header::WWW_AUTHENTICATE,
+            "Basic realm=\"DirectShare\", charset=\"UTF-8\"",
+        )
+        .body(Empty::<Bytes>::new().map_err(|_| unreachable!()).boxed())
+        .unwrap()
+}
+
+fn too_many_requests_page(retry_after: Duration) -> Response<BoxBody<Bytes, io::Error>> {
+    Response::builder()
+        .status(StatusCode::TOO_MANY_REQUESTS)
+        .header(header::RETRY_AFTER, retry_after.as_secs())
+        .body(Empty::<Bytes>::new().map_err(|_| unreachable!()).boxed())
+        .unwrap()
+}
+
+fn range_not_satisfiable_page(total: u64) -> Response<BoxBody<Bytes, io::Error>> {
+    Response::builder()
+        .status(StatusCode::RANGE_NOT_SATISFIABLE)
+        .header(header::CONTENT_RANGE, format!("bytes */{total}"))
+        .header(header::ACCEPT_RANGES, "bytes")
+        .body(Empty::<Bytes>::new().map_err(|_| unreachable!()).boxed())
+        .unwrap()
+}
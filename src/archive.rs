@@ -0,0 +1,998 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::{
+    collections::HashSet,
+    fs::Metadata,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use async_compression::{tokio::write::GzipEncoder, Level};
+use async_zip::{
+    base::write::ZipFileWriter, Compression, ZipDateTime, ZipDateTimeBuilder, ZipEntryBuilder,
+};
+use futures_util::io::AsyncWrite as FuturesAsyncWrite;
+use tokio::{
+    fs::{self, File, ReadDir},
+    io::{duplex, AsyncRead, AsyncWrite, AsyncWriteExt, DuplexStream, ReadBuf},
+    spawn,
+    task::{AbortHandle, JoinHandle},
+};
+use tokio_util::compat::FuturesAsyncWriteCompatExt;
+
+use crate::{
+    config::ArchiveFormat,
+    constants::{FALLBACK_FILENAME, IGNORE_FILE},
+    exclude::ExcludeRules,
+};
+
+const TAR_BLOCK_SIZE: u64 = 512;
+
+#[derive(Debug, Clone)]
+/// Contents of archive
+pub enum ArchiveSource {
+    /// Contents of the directory
+    Directory(PathBuf),
+
+    /// Paths placed at the root of the archive under the names
+    Bundle(Vec<(String, PathBuf)>),
+}
+
+impl ArchiveSource {
+    /// Create bundle source placing each path at its base name
+    pub fn bundle(paths: &[PathBuf]) -> Self {
+        Self::Bundle(bundle_names(paths))
+    }
+
+    /// Exact size of tar archive written by [`ArchiveSource::write`], found by walking every entry
+    pub async fn tar_size(&self, options: &WalkOptions) -> io::Result<u64> {
+        let mut size = TAR_BLOCK_SIZE * 2;
+
+        match self {
+            ArchiveSource::Directory(path) => {
+                size += tar_tree_size(Path::new(""), path, options).await?;
+            }
+
+            ArchiveSource::Bundle(entries) => {
+                for (name, path) in entries {
+                    let meta = fs::metadata(path).await?;
+                    let name = Path::new(name);
+
+                    if meta.is_dir() {
+                        size += tar_entry_size(name, 0);
+                        size += tar_tree_size(name, path, options).await?;
+                    } else {
+                        size += tar_entry_size(name, meta.len());
+                    }
+                }
+            }
+        }
+
+        Ok(size)
+    }
+
+    /// Latest modified time of the shared paths
+    pub async fn modified(&self) -> io::Result<SystemTime> {
+        let paths: Vec<&Path> = match self {
+            ArchiveSource::Directory(path) => vec![path],
+            ArchiveSource::Bundle(entries) => {
+                entries.iter().map(|(_, path)| path.as_path()).collect()
+            }
+        };
+
+        let mut modified = UNIX_EPOCH;
+        for path in paths {
+            modified = modified.max(fs::metadata(path).await?.modified()?);
+        }
+
+        Ok(modified)
+    }
+
+    /// Write archive of the source in `format`, compressed with `gzip_level` for `TarGz`.
+    /// The writer is shut down after the archive is written.
+    /// Entries are walked with `options`.
+    pub async fn write<W: AsyncWrite + Unpin + Send + 'static>(
+        &self,
+        format: ArchiveFormat,
+        gzip_level: u32,
+        writer: W,
+        options: &WalkOptions,
+    ) -> io::Result<()> {
+        match (format, self) {
+            (ArchiveFormat::TarGz, ArchiveSource::Directory(path)) => {
+                write_tar(gzip_writer(writer, gzip_level), path, options).await
+            }
+            (ArchiveFormat::TarGz, ArchiveSource::Bundle(entries)) => {
+                write_tar_bundle(gzip_writer(writer, gzip_level), entries, options).await
+            }
+            (ArchiveFormat::Tar, ArchiveSource::Directory(path)) => {
+                write_tar(writer, path, options).await
+            }
+            (ArchiveFormat::Zip, ArchiveSource::Directory(path)) => {
+                write_zip(writer, path, options).await
+            }
+            (ArchiveFormat::Tar, ArchiveSource::Bundle(entries)) => {
+                write_tar_bundle(writer, entries, options).await
+            }
+            (ArchiveFormat::Zip, ArchiveSource::Bundle(entries)) => {
+                write_zip_bundle(writer, entries, options).await
+            }
+        }
+    }
+
+    /// Generate archive in background task, skipping the first `offset` bytes.
+    /// `buffer_size` bytes are buffered until read from the returned reader.
+    pub fn spawn(
+        self,
+        format: ArchiveFormat,
+        gzip_level: u32,
+        options: WalkOptions,
+        offset: u64,
+        buffer_size: usize,
+        name: String,
+    ) -> ArchiveReader {
+        let (tx, reader) = duplex(buffer_size);
+
+        let task = spawn({
+            let name = name.clone();
+
+            async move {
+                let writer = SkipWrite::new(tx, offset);
+                if let Err(err) = self.write(format, gzip_level, writer, &options).await {
+                    log::warn!("archive generation failed archive: {name} err: {err}");
+                }
+            }
+        });
+
+        ArchiveReader {
+            reader,
+            task,
+            name,
+            done: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Reader of archive generated by [`ArchiveSource::spawn`].
+/// Generation is aborted if dropped before reading the whole archive.
+pub struct ArchiveReader {
+    reader: DuplexStream,
+    task: JoinHandle<()>,
+    name: String,
+    done: bool,
+}
+
+impl ArchiveReader {
+    /// Handle of the generating task
+    pub fn abort_handle(&self) -> AbortHandle {
+        self.task.abort_handle()
+    }
+}
+
+impl AsyncRead for ArchiveReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        let poll = Pin::new(&mut self.reader).poll_read(cx, buf);
+
+        if let Poll::Ready(Ok(())) = poll {
+            if buf.remaining() != 0 && buf.filled().len() == filled {
+                self.done = true;
+            }
+        }
+
+        poll
+    }
+}
+
+impl Drop for ArchiveReader {
+    fn drop(&mut self) {
+        if !self.done && !self.task.is_finished() {
+            self.task.abort();
+            log::info!(
+                "archive generation aborted, reader closed archive: {}",
+                self.name
+            );
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Writer discarding the first bytes, used for resuming archive download
+pub struct SkipWrite<W> {
+    inner: W,
+    remaining: u64,
+}
+
+impl<W> SkipWrite<W> {
+    pub const fn new(inner: W, skip: u64) -> Self {
+        Self {
+            inner,
+            remaining: skip,
+        }
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for SkipWrite<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        if this.remaining > 0 {
+            let skipped = this.remaining.min(buf.len() as u64);
+            this.remaining -= skipped;
+
+            return Poll::Ready(Ok(skipped as usize));
+        }
+
+        Pin::new(&mut this.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+#[derive(Debug)]
+/// Reader or writer of archive failing with [`ErrorKind::FileTooLarge`] once more than `limit` bytes are transferred
+pub struct SizeLimit<T> {
+    inner: T,
+    limit: u64,
+    remaining: u64,
+    name: String,
+}
+
+impl<T> SizeLimit<T> {
+    pub const fn new(inner: T, limit: u64, name: String) -> Self {
+        Self {
+            inner,
+            limit,
+            remaining: limit,
+            name,
+        }
+    }
+
+    fn exceeded(&self) -> io::Error {
+        log::warn!(
+            "archive exceeded max_archive_size, aborting archive: {} limit: {}",
+            self.name,
+            self.limit
+        );
+
+        io::Error::new(ErrorKind::FileTooLarge, "archive exceeded max_archive_size")
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for SizeLimit<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        // Read one more byte at the limit to find out if there is more
+        let len = this.remaining.min(buf.remaining() as u64).max(1) as usize;
+        let mut limited = ReadBuf::new(buf.initialize_unfilled_to(len));
+        ready!(Pin::new(&mut this.inner).poll_read(cx, &mut limited))?;
+
+        let read = limited.filled().len();
+        if read as u64 > this.remaining {
+            return Poll::Ready(Err(this.exceeded()));
+        }
+
+        this.remaining -= read as u64;
+        buf.advance(read);
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for SizeLimit<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        if this.remaining == 0 && !buf.is_empty() {
+            return Poll::Ready(Err(this.exceeded()));
+        }
+
+        let len = this.remaining.min(buf.len() as u64) as usize;
+        let written = ready!(Pin::new(&mut this.inner).poll_write(cx, &buf[..len]))?;
+        this.remaining -= written as u64;
+
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+/// Wrap `writer` compressing written data with gzip
+fn gzip_writer<W: AsyncWrite>(writer: W, level: u32) -> GzipEncoder<W> {
+    GzipEncoder::with_quality(writer, Level::Precise(level as i32))
+}
+
+/// Write tarball archive of directory
+pub async fn write_tar<W: AsyncWrite + Unpin + Send + 'static>(
+    writer: W,
+    path: &Path,
+    options: &WalkOptions,
+) -> io::Result<()> {
+    let mut ar = tokio_tar::Builder::new_non_terminated(writer);
+    append_tar_tree(&mut ar, Path::new(""), path, options).await?;
+    ar.into_inner().await?.shutdown().await?;
+
+    Ok(())
+}
+
+/// Write tarball archive containing each path under the name
+pub async fn write_tar_bundle<W: AsyncWrite + Unpin + Send + 'static>(
+    writer: W,
+    entries: &[(String, PathBuf)],
+    options: &WalkOptions,
+) -> io::Result<()> {
+    let mut ar = tokio_tar::Builder::new_non_terminated(writer);
+    for (name, path) in entries {
+        if fs::metadata(path).await?.is_dir() {
+            ar.append_dir(name, path).await?;
+            append_tar_tree(&mut ar, Path::new(name), path, options).await?;
+        } else {
+            ar.append_path_with_name(path, name).await?;
+        }
+    }
+    ar.into_inner().await?.shutdown().await?;
+
+    Ok(())
+}
+
+/// Append every entry under `root` prefixed with `prefix`
+async fn append_tar_tree<W: AsyncWrite + Unpin + Send + 'static>(
+    ar: &mut tokio_tar::Builder<W>,
+    prefix: &Path,
+    root: &Path,
+    options: &WalkOptions,
+) -> io::Result<()> {
+    let mut walker = Walker::new(root, options).await?;
+    while let Some(entry) = walker.next().await? {
+        let name = prefix.join(&entry.relative);
+
+        if entry.meta.is_dir() {
+            ar.append_dir(name, &entry.path).await?;
+        } else {
+            ar.append_path_with_name(&entry.path, name).await?;
+        }
+    }
+    log_skipped(&walker, root);
+
+    Ok(())
+}
+
+/// Size of every entry under `root` prefixed with `prefix`
+async fn tar_tree_size(prefix: &Path, root: &Path, options: &WalkOptions) -> io::Result<u64> {
+    let mut size = 0;
+
+    let mut walker = Walker::new(root, options).await?;
+    while let Some(entry) = walker.next().await? {
+        let len = if entry.meta.is_dir() {
+            0
+        } else {
+            entry.meta.len()
+        };
+
+        size += tar_entry_size(&prefix.join(&entry.relative), len);
+    }
+
+    Ok(size)
+}
+
+/// Size of tar entry named `name` with `len` bytes of content
+fn tar_entry_size(name: &Path, len: u64) -> u64 {
+    /// Longest name fits in gnu header
+    const MAX_NAME_LEN: u64 = 100;
+
+    let name_len = name.as_os_str().len() as u64;
+
+    // Long name is written as an extra null terminated entry before the header
+    let long_name = if name_len > MAX_NAME_LEN {
+        TAR_BLOCK_SIZE + padded_len(name_len + 1)
+    } else {
+        0
+    };
+
+    long_name + TAR_BLOCK_SIZE + padded_len(len)
+}
+
+/// Length padded to tar block size
+const fn padded_len(len: u64) -> u64 {
+    len.div_ceil(TAR_BLOCK_SIZE) * TAR_BLOCK_SIZE
+}
+
+/// Write zip archive of directory
+pub async fn write_zip<W: AsyncWrite + Unpin>(
+    writer: W,
+    path: &Path,
+    options: &WalkOptions,
+) -> io::Result<()> {
+    let mut ar = ZipFileWriter::with_tokio(writer);
+
+    append_zip_tree(&mut ar, Path::new(""), path, options).await?;
+
+    ar.close()
+        .await
+        .map_err(io::Error::other)?
+        .into_inner()
+        .shutdown()
+        .await?;
+
+    Ok(())
+}
+
+/// Write zip archive containing each path under the name
+pub async fn write_zip_bundle<W: AsyncWrite + Unpin>(
+    writer: W,
+    entries: &[(String, PathBuf)],
+    options: &WalkOptions,
+) -> io::Result<()> {
+    let mut ar = ZipFileWriter::with_tokio(writer);
+
+    for (name, path) in entries {
+        let meta = fs::metadata(path).await?;
+        append_zip_entry(&mut ar, Path::new(name), path, &meta).await?;
+
+        if meta.is_dir() {
+            append_zip_tree(&mut ar, Path::new(name), path, options).await?;
+        }
+    }
+
+    ar.close()
+        .await
+        .map_err(io::Error::other)?
+        .into_inner()
+        .shutdown()
+        .await?;
+
+    Ok(())
+}
+
+/// Append every entry under `root` prefixed with `prefix`
+async fn append_zip_tree<W: FuturesAsyncWrite + Unpin>(
+    ar: &mut ZipFileWriter<W>,
+    prefix: &Path,
+    root: &Path,
+    options: &WalkOptions,
+) -> io::Result<()> {
+    let mut walker = Walker::new(root, options).await?;
+    while let Some(entry) = walker.next().await? {
+        append_zip_entry(ar, &prefix.join(&entry.relative), &entry.path, &entry.meta).await?;
+    }
+    log_skipped(&walker, root);
+
+    Ok(())
+}
+
+fn log_skipped(walker: &Walker, root: &Path) {
+    if walker.skipped_hidden() > 0 {
+        log::info!(
+            "skipped hidden entries count: {} path: {}",
+            walker.skipped_hidden(),
+            root.display()
+        );
+    }
+
+    if walker.excluded() > 0 {
+        log::info!(
+            "excluded entries count: {} path: {}",
+            walker.excluded(),
+            root.display()
+        );
+    }
+}
+
+async fn append_zip_entry<W: FuturesAsyncWrite + Unpin>(
+    ar: &mut ZipFileWriter<W>,
+    relative: &Path,
+    path: &Path,
+    meta: &Metadata,
+) -> io::Result<()> {
+    let name = zip_entry_name(relative, meta.is_dir());
+    let builder = ZipEntryBuilder::new(name.into(), Compression::Deflate)
+        .last_modification_date(zip_date(meta.modified().unwrap_or(UNIX_EPOCH)));
+
+    if meta.is_dir() {
+        return ar
+            .write_entry_whole(builder, &[])
+            .await
+            .map_err(io::Error::other);
+    }
+
+    let mut file = File::open(path).await?;
+    let mut entry_writer = ar
+        .write_entry_stream(builder)
+        .await
+        .map_err(io::Error::other)?
+        .compat_write();
+
+    tokio::io::copy(&mut file, &mut entry_writer).await?;
+    entry_writer
+        .into_inner()
+        .close()
+        .await
+        .map_err(io::Error::other)
+}
+
+/// Name each path with its base name, suffixing number on collision
+pub fn bundle_names(paths: &[PathBuf]) -> Vec<(String, PathBuf)> {
+    let mut used = HashSet::new();
+
+    paths
+        .iter()
+        .map(|path| {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or(FALLBACK_FILENAME.into());
+
+            let mut unique = name.clone();
+            let mut count = 1;
+            while !used.insert(unique.clone()) {
+                unique = match name.rsplit_once('.') {
+                    Some((stem, ext)) if !stem.is_empty() => format!("{stem} ({count}).{ext}"),
+                    _ => format!("{name} ({count})"),
+                };
+                count += 1;
+            }
+
+            (unique, path.clone())
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+/// Entry found while walking directory
+pub struct WalkEntry {
+    /// Path of the entry
+    pub path: PathBuf,
+
+    /// Path relative to walking root
+    pub relative: PathBuf,
+
+    pub meta: Metadata,
+}
+
+#[derive(Debug, Clone, Default)]
+/// Options deciding which entries are walked
+pub struct WalkOptions {
+    /// Follow symlinks pointing outside of walking root
+    pub follow_symlinks: bool,
+
+    /// Include entries whose name starts with `.`
+    pub include_hidden: bool,
+
+    /// Excluded paths, extended with patterns in ignore file of walking root
+    pub exclude: ExcludeRules,
+}
+
+#[derive(Debug)]
+/// Depth first directory walker.
+/// Only keeps opened directories of current depth in memory.
+pub struct Walker {
+    /// Canonical path of walking root
+    root: PathBuf,
+
+    options: WalkOptions,
+
+    /// Exclude rules of options and ignore file
+    exclude: ExcludeRules,
+
+    /// Hidden entries skipped while walking
+    skipped_hidden: usize,
+
+    /// Entries skipped by exclude rules
+    excluded: usize,
+
+    /// Canonical paths of visited directories to prevent cycles
+    visited: HashSet<PathBuf>,
+
+    stack: Vec<(PathBuf, ReadDir)>,
+}
+
+impl Walker {
+    pub async fn new(root: &Path, options: &WalkOptions) -> io::Result<Self> {
+        let canonical_root = fs::canonicalize(root).await?;
+
+        let mut exclude = options.exclude.clone();
+        match fs::read_to_string(root.join(IGNORE_FILE)).await {
+            Ok(patterns) => exclude.extend(ExcludeRules::new(patterns.lines())),
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => log::warn!(
+                "cannot read ignore file path: {} err: {err}",
+                root.join(IGNORE_FILE).display()
+            ),
+        }
+
+        Ok(Self {
+            visited: HashSet::from([canonical_root.clone()]),
+            root: canonical_root,
+            options: options.clone(),
+            exclude,
+            skipped_hidden: 0,
+            excluded: 0,
+            stack: vec![(PathBuf::new(), fs::read_dir(root).await?)],
+        })
+    }
+
+    /// Next entry of the tree. Directories are returned before its children.
+    pub async fn next(&mut self) -> io::Result<Option<WalkEntry>> {
+        while let Some((parent, read_dir)) = self.stack.last_mut() {
+            let Some(entry) = read_dir.next_entry().await? else {
+                self.stack.pop();
+                continue;
+            };
+
+            if !self.options.include_hidden
+                && entry.file_name().as_encoded_bytes().starts_with(b".")
+            {
+                self.skipped_hidden += 1;
+                continue;
+            }
+
+            let relative = parent.join(entry.file_name());
+            let path = entry.path();
+
+            let meta = entry.metadata().await?;
+            let meta = if meta.is_symlink() {
+                match self.resolve_symlink(&path).await {
+                    Some(meta) => meta,
+                    None => continue,
+                }
+            } else {
+                meta
+            };
+
+            // Excluded directory is skipped with its children
+            if self.exclude.is_excluded(&relative, meta.is_dir()) {
+                log::debug!("excluding path: {}", path.display());
+                self.excluded += 1;
+                continue;
+            }
+
+            if meta.is_dir() {
+                if !self.visited.insert(fs::canonicalize(&path).await?) {
+                    log::warn!("skipping directory cycle path: {}", path.display());
+                    continue;
+                }
+
+                self.stack
+                    .push((relative.clone(), fs::read_dir(&path).await?));
+            }
+
+            return Ok(Some(WalkEntry {
+                path,
+                relative,
+                meta,
+            }));
+        }
+
+        Ok(None)
+    }
+
+    /// Count of hidden entries skipped so far
+    pub const fn skipped_hidden(&self) -> usize {
+        self.skipped_hidden
+    }
+
+    /// Count of entries skipped by exclude rules so far
+    pub const fn excluded(&self) -> usize {
+        self.excluded
+    }
+
+    /// Metadata of symlink target. Returns `None` if the link should be skipped.
+    async fn resolve_symlink(&self, path: &Path) -> Option<Metadata> {
+        let target = match fs::canonicalize(path).await {
+            Ok(target) => target,
+            Err(err) => {
+                log::warn!(
+                    "skipping broken symlink path: {} err: {err}",
+                    path.display()
+                );
+                return None;
+            }
+        };
+
+        if !self.options.follow_symlinks && !target.starts_with(&self.root) {
+            log::warn!(
+                "skipping symlink escaping shared directory path: {} target: {}",
+                path.display(),
+                target.display()
+            );
+            return None;
+        }
+
+        fs::metadata(&target).await.ok()
+    }
+}
+
+fn zip_entry_name(relative: &Path, is_dir: bool) -> String {
+    let mut name = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    if is_dir {
+        name.push('/');
+    }
+
+    name
+}
+
+/// Convert system time to zip date time in UTC
+fn zip_date(time: SystemTime) -> ZipDateTime {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+
+    let days = (secs / 86400) as i64;
+    let secs_of_day = (secs % 86400) as u32;
+
+    // Civil date from days since unix epoch
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = (yoe + era * 400 + i64::from(month <= 2)) as i32;
+
+    // Zip date can only represent years between 1980 and 2107
+    if year < 1980 {
+        return ZipDateTimeBuilder::new().year(1980).month(1).day(1).build();
+    }
+
+    ZipDateTimeBuilder::new()
+        .year(year.min(2107))
+        .month(month)
+        .day(day)
+        .hour(secs_of_day / 3600)
+        .minute(secs_of_day % 3600 / 60)
+        .second(secs_of_day % 60)
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::ErrorKind,
+        path::{Path, PathBuf},
+        time::Duration,
+    };
+
+    use async_compression::tokio::bufread::GzipDecoder;
+    use tokio::{
+        fs,
+        io::{AsyncReadExt, AsyncWriteExt, BufReader},
+        spawn,
+        task::yield_now,
+        time::timeout,
+    };
+
+    use crate::{
+        archive::{bundle_names, ArchiveSource, SizeLimit, WalkOptions, Walker},
+        config::ArchiveFormat,
+    };
+
+    #[tokio::test]
+    pub async fn tar_size_test() {
+        let root = std::env::temp_dir().join("direct_share_tar_size_test");
+        let _ = fs::remove_dir_all(&root).await;
+
+        let long_dir = root.join("d".repeat(80));
+        fs::create_dir_all(&long_dir).await.unwrap();
+        fs::write(root.join("small.txt"), "hello").await.unwrap();
+        fs::write(long_dir.join("f".repeat(40)), vec![0; 1000])
+            .await
+            .unwrap();
+
+        let source = ArchiveSource::Directory(root.clone());
+        let size = source.tar_size(&WalkOptions::default()).await.unwrap();
+
+        let (tx, mut rx) = tokio::io::duplex(65536);
+        spawn(async move {
+            source
+                .write(ArchiveFormat::Tar, 6, tx, &WalkOptions::default())
+                .await
+        });
+
+        let mut data = Vec::new();
+        rx.read_to_end(&mut data).await.unwrap();
+
+        assert_eq!(size, data.len() as u64);
+
+        fs::remove_dir_all(root).await.unwrap();
+    }
+
+    #[tokio::test]
+    pub async fn tar_gz_test() {
+        let root = std::env::temp_dir().join("direct_share_tar_gz_test");
+        let _ = fs::remove_dir_all(&root).await;
+
+        fs::create_dir_all(&root).await.unwrap();
+        fs::write(root.join("text.txt"), "hello ".repeat(1000))
+            .await
+            .unwrap();
+
+        let source = ArchiveSource::Directory(root.clone());
+        let size = source.tar_size(&WalkOptions::default()).await.unwrap();
+
+        let (tx, rx) = tokio::io::duplex(65536);
+        spawn(async move {
+            source
+                .write(ArchiveFormat::TarGz, 9, tx, &WalkOptions::default())
+                .await
+        });
+
+        let mut data = Vec::new();
+        GzipDecoder::new(BufReader::new(rx))
+            .read_to_end(&mut data)
+            .await
+            .unwrap();
+
+        assert_eq!(size, data.len() as u64);
+
+        fs::remove_dir_all(root).await.unwrap();
+    }
+
+    #[tokio::test]
+    pub async fn hidden_test() {
+        let root = std::env::temp_dir().join("direct_share_hidden_test");
+        let _ = fs::remove_dir_all(&root).await;
+
+        fs::create_dir_all(root.join(".git")).await.unwrap();
+        fs::write(root.join(".git").join("HEAD"), "ref")
+            .await
+            .unwrap();
+        fs::write(root.join(".env"), "secret").await.unwrap();
+        fs::write(root.join("a.txt"), "hello").await.unwrap();
+
+        async fn walk(root: &Path, options: &WalkOptions) -> (Vec<PathBuf>, usize) {
+            let mut walker = Walker::new(root, options).await.unwrap();
+
+            let mut entries = Vec::new();
+            while let Some(entry) = walker.next().await.unwrap() {
+                entries.push(entry.relative);
+            }
+            entries.sort();
+
+            (entries, walker.skipped_hidden())
+        }
+
+        assert_eq!(
+            walk(&root, &WalkOptions::default()).await,
+            (vec![PathBuf::from("a.txt")], 2)
+        );
+
+        let options = WalkOptions {
+            include_hidden: true,
+            ..Default::default()
+        };
+        assert_eq!(walk(&root, &options).await.0.len(), 4);
+
+        fs::remove_dir_all(root).await.unwrap();
+    }
+
+    #[tokio::test]
+    pub async fn size_limit_test() {
+        let mut data = Vec::new();
+        let err = SizeLimit::new(&b"hello world"[..], 5, "read".into())
+            .read_to_end(&mut data)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::FileTooLarge);
+
+        let mut writer = SizeLimit::new(Vec::new(), 5, "write".into());
+        let err = writer.write_all(b"hello world").await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::FileTooLarge);
+
+        let mut writer = SizeLimit::new(Vec::new(), 5, "exact".into());
+        writer.write_all(b"hello").await.unwrap();
+    }
+
+    #[tokio::test]
+    pub async fn abort_test() {
+        let root = std::env::temp_dir().join("direct_share_abort_test");
+        let _ = fs::remove_dir_all(&root).await;
+
+        fs::create_dir_all(&root).await.unwrap();
+        fs::write(root.join("large.bin"), vec![0; 1024 * 1024])
+            .await
+            .unwrap();
+
+        let mut reader = ArchiveSource::Directory(root.clone()).spawn(
+            ArchiveFormat::Tar,
+            6,
+            WalkOptions::default(),
+            0,
+            1024,
+            "abort".to_string(),
+        );
+
+        let mut buf = [0; 512];
+        reader.read_exact(&mut buf).await.unwrap();
+
+        let handle = reader.abort_handle();
+        drop(reader);
+
+        timeout(Duration::from_secs(5), async {
+            while !handle.is_finished() {
+                yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+
+        fs::remove_dir_all(root).await.unwrap();
+    }
+
+    #[test]
+    pub fn bundle_names_test() {
+        let paths: Vec<PathBuf> = [
+            "a/photo.jpg",
+            "b/photo.jpg",
+            "c/photo.jpg",
+            "docs",
+            "x/docs",
+        ]
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+
+        let names: Vec<String> = bundle_names(&paths)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+
+        assert_eq!(
+            names,
+            [
+                "photo.jpg",
+                "photo (1).jpg",
+                "photo (2).jpg",
+                "docs",
+                "docs (1)"
+            ]
+        );
+    }
+}
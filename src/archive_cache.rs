@@ -0,0 +1,239 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::{
+    collections::HashMap,
+    io,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+use sha2::{Digest, Sha256};
+use tokio::{
+    fs::{self, File},
+    sync::Mutex as AsyncMutex,
+};
+
+use crate::{
+    archive::{ArchiveSource, SizeLimit, WalkOptions},
+    auth::to_hex,
+    config::ArchiveFormat,
+};
+
+#[derive(Debug)]
+/// Archives generated to files once, so they can be served like regular files
+pub struct ArchiveCache {
+    dir: PathBuf,
+    ttl: Duration,
+    entries: Mutex<HashMap<String, Arc<AsyncMutex<Option<CachedArchive>>>>>,
+}
+
+#[derive(Debug)]
+struct CachedArchive {
+    path: PathBuf,
+
+    /// Modified time of the source when the archive was generated
+    source_modified: SystemTime,
+
+    last_used: Instant,
+}
+
+impl ArchiveCache {
+    /// Create cache storing archives in `dir`. Archives unused for `ttl` are removed.
+    pub fn new(dir: PathBuf, ttl: Duration) -> Self {
+        Self {
+            dir,
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Path of generated archive of `source`.
+    /// The archive is generated again if the source was modified since.
+    /// Generation fails if the archive grows larger than `max_size`.
+    pub async fn get(
+        &self,
+        source: &ArchiveSource,
+        format: ArchiveFormat,
+        gzip_level: u32,
+        options: &WalkOptions,
+        max_size: Option<u64>,
+    ) -> io::Result<PathBuf> {
+        let id = cache_id(source, format, gzip_level, options);
+
+        let slot = self
+            .entries
+            .lock()
+            .unwrap()
+            .entry(id.clone())
+            .or_default()
+            .clone();
+
+        // Concurrent requests wait for the first one generating the archive
+        let mut slot = slot.lock().await;
+
+        let modified = source.modified().await?;
+        if let Some(ref mut cached) = *slot {
+            if cached.source_modified == modified && fs::try_exists(&cached.path).await? {
+                cached.last_used = Instant::now();
+                return Ok(cached.path.clone());
+            }
+        }
+
+        fs::create_dir_all(&self.dir).await?;
+
+        let path = self.dir.join(format!("{id}.{}", format.extension()));
+        let partial = path.with_extension("part");
+
+        log::info!("generating archive path: {}", path.display());
+        let file = File::create(&partial).await?;
+        let res = match max_size {
+            Some(limit) => {
+                let name = path.display().to_string();
+                let file = SizeLimit::new(file, limit, name);
+                source.write(format, gzip_level, file, options).await
+            }
+            None => source.write(format, gzip_level, file, options).await,
+        };
+
+        if let Err(err) = res {
+            let _ = fs::remove_file(&partial).await;
+            return Err(err);
+        }
+        fs::rename(&partial, &path).await?;
+
+        *slot = Some(CachedArchive {
+            path: path.clone(),
+            source_modified: modified,
+            last_used: Instant::now(),
+        });
+
+        Ok(path)
+    }
+
+    /// Remove archives unused for ttl
+    pub async fn remove_stale(&self) {
+        let slots: Vec<_> = self.entries.lock().unwrap().values().cloned().collect();
+
+        for slot in slots {
+            // Archive being generated or checked is in use
+            let Ok(mut slot) = slot.try_lock() else {
+                continue;
+            };
+
+            if let Some(ref cached) = *slot {
+                if cached.last_used.elapsed() < self.ttl {
+                    continue;
+                }
+
+                if let Err(err) = fs::remove_file(&cached.path).await {
+                    log::warn!(
+                        "cannot remove cached archive path: {} err: {err}",
+                        cached.path.display()
+                    );
+                }
+
+                *slot = None;
+            }
+        }
+    }
+}
+
+/// File name of archive identifying its contents
+fn cache_id(
+    source: &ArchiveSource,
+    format: ArchiveFormat,
+    gzip_level: u32,
+    options: &WalkOptions,
+) -> String {
+    let mut hasher = Sha256::new();
+
+    hasher.update(format.extension());
+    if format == ArchiveFormat::TarGz {
+        hasher.update(gzip_level.to_le_bytes());
+    }
+    hasher.update([options.follow_symlinks as u8, options.include_hidden as u8]);
+    for pattern in options.exclude.patterns() {
+        hasher.update(pattern);
+        hasher.update([0]);
+    }
+
+    match source {
+        ArchiveSource::Directory(path) => {
+            hasher.update(path.as_os_str().as_encoded_bytes());
+        }
+
+        ArchiveSource::Bundle(entries) => {
+            for (name, path) in entries {
+                hasher.update(name);
+                hasher.update([0]);
+                hasher.update(path.as_os_str().as_encoded_bytes());
+                hasher.update([0]);
+            }
+        }
+    }
+
+    to_hex(&hasher.finalize()[..16])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::fs;
+
+    use crate::{
+        archive::{ArchiveSource, WalkOptions},
+        archive_cache::ArchiveCache,
+        config::ArchiveFormat,
+    };
+
+    #[tokio::test]
+    pub async fn reuse_test() {
+        let root = std::env::temp_dir().join("direct_share_archive_cache_test");
+        let _ = fs::remove_dir_all(&root).await;
+
+        let shared = root.join("shared");
+        fs::create_dir_all(&shared).await.unwrap();
+        fs::write(shared.join("a.txt"), "hello").await.unwrap();
+
+        let cache = ArchiveCache::new(root.join("cache"), Duration::ZERO);
+        let source = ArchiveSource::Directory(shared.clone());
+
+        let path = cache
+            .get(
+                &source,
+                ArchiveFormat::Tar,
+                6,
+                &WalkOptions::default(),
+                None,
+            )
+            .await
+            .unwrap();
+        let len = fs::metadata(&path).await.unwrap().len();
+        assert_eq!(len, source.tar_size(&WalkOptions::default()).await.unwrap());
+
+        // Unchanged source is not generated again
+        fs::write(&path, "cached").await.unwrap();
+        let path = cache
+            .get(
+                &source,
+                ArchiveFormat::Tar,
+                6,
+                &WalkOptions::default(),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(fs::read_to_string(&path).await.unwrap(), "cached");
+
+        cache.remove_stale().await;
+        assert!(!fs::try_exists(&path).await.unwrap());
+
+        fs::remove_dir_all(root).await.unwrap();
+    }
+}
@@ -0,0 +1,125 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::fmt::Write;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rand::{thread_rng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Credentials for basic authentication
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl Credentials {
+    /// Check `Authorization` header value matches the credentials
+    pub fn verify(&self, authorization: &str) -> bool {
+        let Some((username, password)) = parse_basic(authorization) else {
+            return false;
+        };
+
+        // Compare both to not leak which one is wrong
+        constant_time_eq(username.as_bytes(), self.username.as_bytes())
+            & constant_time_eq(password.as_bytes(), self.password.as_bytes())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Salted password hash
+pub struct PasswordHash {
+    salt: String,
+    hash: String,
+}
+
+impl PasswordHash {
+    pub fn new(password: &str) -> Self {
+        let mut salt = [0_u8; 16];
+        thread_rng().fill_bytes(&mut salt);
+        let salt = to_hex(&salt);
+
+        let hash = hash_password(&salt, password);
+        Self { salt, hash }
+    }
+
+    pub fn verify(&self, password: &str) -> bool {
+        constant_time_eq(
+            hash_password(&self.salt, password).as_bytes(),
+            self.hash.as_bytes(),
+        )
+    }
+}
+
+fn hash_password(salt: &str, password: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(password.as_bytes());
+
+    to_hex(&hasher.finalize())
+}
+
+pub fn to_hex(data: &[u8]) -> String {
+    let mut hex = String::with_capacity(data.len() * 2);
+    for byte in data {
+        let _ = write!(hex, "{byte:02x}");
+    }
+
+    hex
+}
+
+/// Parse basic `Authorization` header value into username and password
+pub fn parse_basic(authorization: &str) -> Option<(String, String)> {
+    let (scheme, encoded) = authorization.trim().split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("basic") {
+        return None;
+    }
+
+    let decoded = String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?;
+    let (username, password) = decoded.split_once(':')?;
+
+    Some((username.to_string(), password.to_string()))
+}
+
+/// Compare bytes in constant time regardless of where they differ
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::auth::{Credentials, PasswordHash};
+
+    #[test]
+    pub fn verify_test() {
+        let credentials = Credentials {
+            username: "user".into(),
+            password: "pass".into(),
+        };
+
+        // user:pass
+        assert!(credentials.verify("Basic dXNlcjpwYXNz"));
+        // user:wrong
+        assert!(!credentials.verify("Basic dXNlcjp3cm9uZw=="));
+        assert!(!credentials.verify("Bearer dXNlcjpwYXNz"));
+        assert!(!credentials.verify("Basic !!!"));
+    }
+
+    #[test]
+    pub fn password_hash_test() {
+        let hash = PasswordHash::new("secret");
+
+        assert!(hash.verify("secret"));
+        assert!(!hash.verify("Secret"));
+        assert!(!hash.verify(""));
+    }
+}
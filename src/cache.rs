@@ -0,0 +1,138 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::{
+    io,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use sha2::{Digest, Sha256};
+use tokio::{fs::File, io::AsyncReadExt};
+
+use crate::{auth::to_hex, constants::FILE_BUF_SIZE};
+
+/// Value for `Last-Modified` header
+pub fn last_modified(modified: SystemTime) -> String {
+    httpdate::fmt_http_date(modified)
+}
+
+/// Check if resource modified at `modified` is newer than `If-Modified-Since` header value.
+/// Malformed date is treated as modified.
+pub fn is_modified_since(value: &str, modified: SystemTime) -> bool {
+    let Ok(since) = httpdate::parse_http_date(value.trim()) else {
+        return true;
+    };
+
+    // Http date has second precision
+    let modified = modified
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| UNIX_EPOCH + Duration::from_secs(duration.as_secs()))
+        .unwrap_or(modified);
+
+    modified > since
+}
+
+/// Weak entity tag derived from size and modified time
+pub fn weak_etag(len: u64, modified: Option<SystemTime>) -> String {
+    let modified = modified
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .unwrap_or_default();
+
+    format!("W/\"{len:x}-{:x}\"", modified.as_nanos())
+}
+
+/// Strong entity tag derived from sha256 `digest` of file content
+pub fn strong_etag(digest: &[u8; 32]) -> String {
+    format!("\"{}\"", to_hex(digest))
+}
+
+/// Entity tag of representation encoded with `encoding`, distinct from the identity one
+pub fn encoded_etag(etag: &str, encoding: &str) -> String {
+    match etag.strip_suffix('"') {
+        Some(tag) => format!("{tag}-{encoding}\""),
+        None => etag.to_string(),
+    }
+}
+
+/// Sha256 hash of file content
+pub async fn sha256_file(path: &Path) -> io::Result<[u8; 32]> {
+    let mut file = File::open(path).await?;
+
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; FILE_BUF_SIZE];
+    loop {
+        let read = file.read(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+
+        hasher.update(&buf[..read]);
+    }
+
+    Ok(hasher.finalize().into())
+}
+
+/// Check if `If-None-Match` header value matches `etag` using weak comparison
+pub fn etag_matches(value: &str, etag: &str) -> bool {
+    fn opaque(tag: &str) -> &str {
+        let tag = tag.trim();
+        tag.strip_prefix("W/").unwrap_or(tag)
+    }
+
+    let value = value.trim();
+    if value == "*" {
+        return true;
+    }
+
+    value.split(',').any(|tag| opaque(tag) == opaque(etag))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::cache::{
+        encoded_etag, etag_matches, is_modified_since, last_modified, strong_etag, weak_etag,
+    };
+
+    #[test]
+    pub fn is_modified_since_test() {
+        let modified = UNIX_EPOCH + Duration::from_millis(784_111_777_500);
+        let value = last_modified(modified);
+
+        assert_eq!(value, "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert!(!is_modified_since(&value, modified));
+        assert!(!is_modified_since(
+            "Mon, 07 Nov 1994 08:49:37 GMT",
+            modified
+        ));
+        assert!(is_modified_since("Sat, 05 Nov 1994 08:49:37 GMT", modified));
+        assert!(is_modified_since("yesterday", modified));
+    }
+
+    #[test]
+    pub fn etag_matches_test() {
+        let etag = weak_etag(1024, Some(UNIX_EPOCH + Duration::from_secs(1)));
+
+        assert_eq!(etag, "W/\"400-3b9aca00\"");
+        assert!(etag_matches(&etag, &etag));
+        assert!(etag_matches("\"400-3b9aca00\"", &etag));
+        assert!(etag_matches("\"abc\", W/\"400-3b9aca00\"", &etag));
+        assert!(etag_matches("*", &etag));
+        assert!(!etag_matches("W/\"400-0\"", &etag));
+    }
+
+    #[test]
+    pub fn encoded_etag_test() {
+        let etag = strong_etag(&[0xab; 32]);
+        let encoded = encoded_etag(&etag, "br");
+
+        assert_eq!(encoded, format!("\"{}-br\"", "ab".repeat(32)));
+        assert!(!etag_matches(&etag, &encoded));
+        assert_eq!(encoded_etag("W/\"400-0\"", "gzip"), "W/\"400-0-gzip\"");
+    }
+}
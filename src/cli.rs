@@ -0,0 +1,284 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::{
+    io::{self, BufRead, IsTerminal},
+    net::IpAddr,
+    num::{NonZeroU16, NonZeroU8},
+    path::{Path, PathBuf},
+};
+
+use clap::Parser;
+
+use crate::{config::DirectShareConfig, constants};
+
+#[derive(Debug, Parser)]
+#[command(about, version = constants::VERSION_INFO)]
+/// Command line arguments
+pub struct Args {
+    /// Files or directories to share. `-` shares bytes piped to stdin
+    pub paths: Vec<PathBuf>,
+
+    /// Share path under custom key, e.g. `--name invoice ./invoice.pdf`
+    #[arg(long, num_args = 2, value_names = ["KEY", "PATH"])]
+    pub name: Vec<String>,
+
+    /// Expand glob patterns like `*.jpg` or `photos/**/*.png` in paths
+    #[arg(long)]
+    pub glob: bool,
+
+    /// Read newline separated paths to share from stdin.
+    /// Enabled implicitly if no path is given and stdin is not a terminal
+    #[arg(long)]
+    pub stdin: bool,
+
+    /// File name of content piped to stdin with `-`
+    #[arg(long, value_name = "NAME", default_value = "stdin")]
+    pub stdin_name: String,
+
+    /// Share every path as one archive link
+    #[arg(long)]
+    pub bundle: bool,
+
+    /// Config file to use instead of the default location
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Port to bind server, overrides config
+    #[arg(long)]
+    pub port: Option<NonZeroU16>,
+
+    /// Address to bind server, overrides config
+    #[arg(long, value_name = "ADDRESS")]
+    pub bind: Option<IpAddr>,
+
+    /// Length of generated keys, overrides config
+    #[arg(long)]
+    pub key_length: Option<NonZeroU8>,
+
+    /// Disable uPnP port mapping for this run
+    #[arg(long)]
+    pub no_upnp: bool,
+
+    /// Copy registered urls to clipboard
+    #[arg(long)]
+    pub copy: bool,
+
+    /// Open first registered url in default browser
+    #[arg(long)]
+    pub open: bool,
+
+    /// Register paths and print urls without starting server
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Print registered links to stdout as json
+    #[arg(long)]
+    pub print_json: bool,
+
+    /// Add DirectShare to Windows "Send To" menu and exit
+    #[arg(long)]
+    pub install_sendto: bool,
+}
+
+impl Args {
+    /// Collect paths to share from arguments and stdin
+    pub fn collect_paths(&self) -> io::Result<Vec<PathBuf>> {
+        let paths = self.paths.iter().filter(|path| !is_stdin_path(path));
+        let mut paths: Vec<PathBuf> = if self.glob {
+            paths.flat_map(|path| expand_glob(path)).collect()
+        } else {
+            paths.cloned().collect()
+        };
+
+        // Stdin is read as content instead when `-` is given
+        if !self.pipes_content() && (self.stdin || (paths.is_empty() && !io::stdin().is_terminal()))
+        {
+            paths.extend(read_paths(io::stdin().lock())?);
+        }
+
+        Ok(paths)
+    }
+
+    /// True if content piped to stdin should be shared
+    pub fn pipes_content(&self) -> bool {
+        self.paths.iter().any(|path| is_stdin_path(path))
+    }
+
+    /// Paths to share under custom keys given with `--name`
+    pub fn named_paths(&self) -> Vec<(String, PathBuf)> {
+        self.name
+            .chunks_exact(2)
+            .map(|pair| (pair[0].clone(), PathBuf::from(&pair[1])))
+            .collect()
+    }
+
+    /// Override config values with arguments
+    pub fn apply(&self, config: &mut DirectShareConfig) {
+        if let Some(port) = self.port {
+            config.port = port;
+        }
+
+        if let Some(bind) = self.bind {
+            config.bind_address = bind;
+        }
+
+        if let Some(key_length) = self.key_length {
+            config.key_length = key_length;
+        }
+
+        if self.bundle {
+            config.bundle = true;
+        }
+
+        if self.no_upnp {
+            config.enable_upnp = false;
+        }
+
+        if self.copy {
+            config.copy_to_clipboard = true;
+        }
+
+        if self.open {
+            config.open_browser = true;
+        }
+    }
+}
+
+/// `-` argument standing for stdin
+fn is_stdin_path(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+/// Expand glob pattern into matching paths
+fn expand_glob(pattern: &Path) -> Vec<PathBuf> {
+    let pattern = pattern.to_string_lossy();
+
+    let matches = match glob::glob(&pattern) {
+        Ok(matches) => matches,
+        Err(err) => {
+            log::warn!("invalid glob pattern: {pattern} err: {err}");
+            return Vec::new();
+        }
+    };
+
+    let paths: Vec<PathBuf> = matches
+        .filter_map(|entry| match entry {
+            Ok(path) => Some(path),
+            Err(err) => {
+                log::warn!("cannot read glob match err: {err}");
+                None
+            }
+        })
+        .collect();
+
+    if paths.is_empty() {
+        log::warn!("glob pattern: {pattern} matched nothing");
+    }
+
+    paths
+}
+
+/// Read newline separated paths. Blank lines and missing paths are skipped
+fn read_paths(reader: impl BufRead) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let path = PathBuf::from(line);
+        if !path.exists() {
+            log::warn!("skipping missing path: {line}");
+            continue;
+        }
+
+        paths.push(path);
+    }
+
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::IpAddr, path::PathBuf};
+
+    use clap::{error::ErrorKind, Parser};
+
+    use crate::{
+        cli::{expand_glob, read_paths, Args},
+        config::DirectShareConfig,
+    };
+
+    #[test]
+    pub fn apply_test() {
+        let args = Args::parse_from([
+            "direct-share",
+            "--port",
+            "8080",
+            "--bind",
+            "::1",
+            "--key-length",
+            "12",
+            "foo.txt",
+        ]);
+
+        let mut config = DirectShareConfig::default();
+        args.apply(&mut config);
+
+        assert_eq!(config.port.get(), 8080);
+        assert_eq!(config.bind_address, "::1".parse::<IpAddr>().unwrap());
+        assert_eq!(config.key_length.get(), 12);
+        assert_eq!(args.paths, vec![PathBuf::from("foo.txt")]);
+    }
+
+    #[test]
+    pub fn pipes_content_test() {
+        let args = Args::parse_from(["direct-share", "Cargo.toml", "-", "--stdin-name", "log.txt"]);
+
+        assert!(args.pipes_content());
+        assert_eq!(args.stdin_name, "log.txt");
+        assert_eq!(
+            args.collect_paths().unwrap(),
+            vec![PathBuf::from("Cargo.toml")]
+        );
+
+        assert!(!Args::parse_from(["direct-share", "Cargo.toml"]).pipes_content());
+    }
+
+    #[test]
+    pub fn version_test() {
+        let err = Args::try_parse_from(["direct-share", "--version"]).unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::DisplayVersion);
+        assert!(err.to_string().contains(env!("CARGO_PKG_VERSION")));
+    }
+
+    #[test]
+    pub fn read_paths_test() {
+        let input = "  Cargo.toml  \n\n\t\nmissing/file\nsrc\n";
+
+        assert_eq!(
+            read_paths(input.as_bytes()).unwrap(),
+            vec![PathBuf::from("Cargo.toml"), PathBuf::from("src")]
+        );
+    }
+
+    #[test]
+    pub fn expand_glob_test() {
+        let paths = expand_glob("src/*.rs".as_ref());
+
+        assert!(paths.contains(&PathBuf::from("src/main.rs")));
+        assert!(paths
+            .iter()
+            .all(|path| path.extension().is_some_and(|ext| ext == "rs")));
+
+        assert!(expand_glob("src/*.nothing".as_ref()).is_empty());
+    }
+}
@@ -4,25 +4,648 @@
  * Copyright (c) storycraft. Licensed under the MIT Licence.
  */
 
-use std::num::{NonZeroU16, NonZeroU8};
+use std::{
+    collections::BTreeMap,
+    net::{IpAddr, Ipv4Addr},
+    num::{NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize},
+    path::{Path, PathBuf},
+};
 
-use serde::{Deserialize, Serialize};
+use igd::PortMappingProtocol;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{
+    auth::Credentials,
+    constants::{CONFIG_DIR, CONFIG_FILE, FILE_BUF_SIZE, TAR_BUF_SIZE},
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 /// App config
 pub struct DirectShareConfig {
+    /// Address that server binds to
+    #[serde(deserialize_with = "deserialize_bind_address")]
+    pub bind_address: IpAddr,
+
     /// Port that can be used to bind server
     pub port: NonZeroU16,
 
-    /// Key length for shorten url
+    /// Try following ports if the port is already in use
+    pub auto_port: bool,
+
+    /// Listen on unix domain socket at the path instead of tcp port
+    pub unix_socket: Option<PathBuf>,
+
+    /// Host name or address advertised in urls instead of local ip, e.g. `share.example.com`
+    pub public_host: Option<String>,
+
+    /// Serve links under the path, e.g. `share` serves `/share/key`
+    pub path_prefix: Option<String>,
+
+    /// Key length for shorten url. Word count if `key_style` is `words`
     pub key_length: NonZeroU8,
+
+    /// How keys are generated
+    pub key_style: KeyStyle,
+
+    /// Characters used for generating keys
+    pub key_alphabet: KeyAlphabet,
+
+    /// Match keys ignoring case. Generated keys use lowercase characters only
+    pub case_insensitive_keys: bool,
+
+    /// Print qr code of registered urls
+    pub show_qr: bool,
+
+    /// Copy registered urls to clipboard
+    pub copy_to_clipboard: bool,
+
+    /// Open first registered url in default browser
+    pub open_browser: bool,
+
+    /// Response to `/favicon.ico` requests, unless a link uses the key
+    pub favicon: Favicon,
+
+    /// File that will be used for 404 page
+    pub default_file: Option<String>,
+
+    /// File that will be used for 503 page while paused
+    pub maintenance_file: Option<String>,
+
+    /// Always download files instead of displaying them in browser
+    pub force_download: bool,
+
+    /// Disposition of files by extension like `pdf`, media type like `text/plain`
+    /// or top level type like `image`. Used unless `force_download` is set, unmatched files are downloaded
+    pub disposition: BTreeMap<String, Disposition>,
+
+    /// How shared directories are served
+    pub directory_mode: DirectoryMode,
+
+    /// Archive format used for downloading directories
+    pub archive_format: ArchiveFormat,
+
+    /// Abort directory archive larger than this many bytes
+    pub max_archive_size: Option<u64>,
+
+    /// Gzip level of `targz` archives, from 1 (fastest) to 9 (smallest)
+    pub archive_gzip_level: u32,
+
+    /// Show a page with archive name and estimated size to browsers before downloading directory
+    pub archive_download_page: bool,
+
+    /// Walk directory before tar download to send its size. Costs a stat of every file
+    pub compute_archive_size: bool,
+
+    /// Generate archive to a file on first download and serve it like a regular file,
+    /// allowing resumed and concurrent downloads. Generated again if the shared paths are modified
+    pub archive_cache: bool,
+
+    /// Directory where archives are generated. System temp directory is used if not set
+    pub archive_cache_dir: Option<PathBuf>,
+
+    /// Seconds until unused generated archive is removed
+    pub archive_cache_ttl_secs: u64,
+
+    /// Follow symlinks pointing outside of shared directories
+    pub follow_symlinks: bool,
+
+    /// Include hidden entries whose name starts with `.` when archiving directories
+    pub include_hidden: bool,
+
+    /// Gitignore style glob patterns excluded when archiving directories, e.g. `node_modules` or `*.log`.
+    /// Patterns in `.directshareignore` of shared directory are added
+    pub exclude: Vec<String>,
+
+    /// Serve index.html of directories instead of archiving or listing them
+    pub serve_index: bool,
+
+    /// Share every path from command line as one archive link
+    pub bundle: bool,
+
+    /// Compress compressible files with brotli or gzip if client supports it
+    pub compression: bool,
+
+    /// Send files without `Content-Length` so files growing while serving, like logs, are sent entirely
+    pub live_files: bool,
+
+    /// Use hash of file content as etag instead of size and modified time.
+    /// Files are read entirely on first request and the hash is cached
+    pub strong_etags: bool,
+
+    /// Send sha256 digest of whole file in `Content-Digest` header.
+    /// Files are read entirely on first request and the digest is cached
+    pub send_digest: bool,
+
+    /// Serve sha256 checksum of shared file at `{key}.sha256` in sha256sum format
+    pub checksums: bool,
+
+    /// Maximum bytes per second sent to each connection
+    pub rate_limit: Option<NonZeroU64>,
+
+    /// Maximum bytes per second sent to all connections combined
+    pub global_rate_limit: Option<NonZeroU64>,
+
+    /// Maximum requests from a single ip in `rate_limit_window_secs`
+    pub rate_limit_requests: Option<NonZeroU32>,
+
+    /// Length of request counting window
+    pub rate_limit_window_secs: u64,
+
+    /// Use client ip reported by `Forwarded` or `X-Forwarded-For` header.
+    /// Enable only behind a trusted reverse proxy, the headers can be spoofed otherwise.
+    pub trust_proxy: bool,
+
+    /// Log level such as `info` or `trace`. `RUST_LOG` env var takes precedence
+    pub log_level: Option<String>,
+
+    /// File where requests are logged
+    pub access_log: Option<PathBuf>,
+
+    /// Size in bytes after the access log is rolled
+    pub access_log_rotate_size: u64,
+
+    /// Serve prometheus metrics at `/metrics` on the port. Disabled if not set
+    pub metrics_port: Option<NonZeroU16>,
+
+    /// Map port on router using uPnP
+    pub enable_upnp: bool,
+
+    /// Protocols mapped with uPnP
+    pub upnp_protocol: UpnpProtocol,
+
+    /// Seconds until uPnP port mapping expires. 0 requests indefinite mapping
+    pub upnp_lease_secs: u32,
+
+    /// Maximum retry count of failed uPnP port mapping
+    pub upnp_max_retries: u32,
+
+    /// Base of linearly increasing delay between uPnP port mapping retries
+    pub upnp_retry_base_secs: u64,
+
+    /// Advertise server on local network using mDNS
+    pub mdns: bool,
+
+    /// Save shared links so they survive restarts
+    pub persist_links: bool,
+
+    /// Seconds until links registered from command line expire
+    pub default_ttl_secs: Option<u64>,
+
+    /// Download count limit of links registered from command line.
+    /// Ranges are ignored for limited links, HEAD and not modified responses are not counted
+    pub default_download_limit: Option<u32>,
+
+    /// Password required to download links registered from command line
+    pub default_password: Option<String>,
+
+    /// Stop server once every link is exhausted or expired
+    pub exit_when_done: bool,
+
+    /// Credentials required to access any file
+    pub auth: Option<Credentials>,
+
+    /// Serve over https using the certificate
+    pub tls: Option<TlsConfig>,
+
+    /// Origin allowed to fetch links from browser, e.g. `https://example.com` or `*`
+    pub cors_allow_origin: Option<String>,
+
+    /// Token for admin api. Admin api is disabled if not set
+    pub admin_token: Option<String>,
+
+    /// List links on landing page to requests with admin token
+    pub landing_show_links: bool,
+
+    /// Accept files from recipients at `/upload`
+    pub allow_upload: bool,
+
+    /// Directory that uploaded files are saved to
+    pub upload_dir: PathBuf,
+
+    /// Maximum size of uploaded file in bytes
+    pub max_upload_size: u64,
+
+    /// Maximum number of connections served concurrently.
+    /// Further connections wait until one closes.
+    pub max_connections: NonZeroUsize,
+
+    /// Seconds to wait for client to send complete request headers. 0 disables timeout
+    pub header_timeout_secs: u64,
+
+    /// Seconds connection may stay without any bytes sent or received. 0 disables timeout
+    pub idle_timeout_secs: u64,
+
+    /// Stop server once no connection was open for the seconds
+    pub idle_shutdown_secs: Option<u64>,
+
+    /// Buffer size in bytes used for streaming files
+    pub file_buffer_size: usize,
+
+    /// Send whole files with `sendfile` on Linux without copying them to userspace.
+    /// Ignored for https, ranges, compression, live files and rate limited connections
+    pub zero_copy: bool,
+
+    /// Buffer size in bytes used for streaming generated archives
+    pub tar_buffer_size: usize,
 }
 
 impl Default for DirectShareConfig {
     fn default() -> Self {
         Self {
+            bind_address: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
             port: NonZeroU16::new(1024).unwrap(),
+            auto_port: false,
+            unix_socket: None,
+            public_host: None,
+            path_prefix: None,
             key_length: NonZeroU8::new(8).unwrap(),
+            key_style: KeyStyle::Random,
+            key_alphabet: KeyAlphabet::Full,
+            case_insensitive_keys: false,
+            show_qr: true,
+            copy_to_clipboard: false,
+            open_browser: false,
+            favicon: Favicon::Icon,
+            default_file: None,
+            maintenance_file: None,
+            force_download: true,
+            disposition: BTreeMap::from([
+                ("image".into(), Disposition::Inline),
+                ("pdf".into(), Disposition::Inline),
+                ("text".into(), Disposition::Inline),
+            ]),
+            directory_mode: DirectoryMode::Tar,
+            archive_format: ArchiveFormat::Tar,
+            max_archive_size: None,
+            archive_gzip_level: 6,
+            archive_download_page: false,
+            compute_archive_size: false,
+            archive_cache: false,
+            archive_cache_dir: None,
+            archive_cache_ttl_secs: 3600,
+            follow_symlinks: false,
+            include_hidden: false,
+            exclude: Vec::new(),
+            serve_index: false,
+            bundle: false,
+            compression: false,
+            live_files: false,
+            strong_etags: false,
+            send_digest: false,
+            checksums: false,
+            rate_limit: None,
+            global_rate_limit: None,
+            rate_limit_requests: None,
+            rate_limit_window_secs: 60,
+            trust_proxy: false,
+            log_level: None,
+            access_log: None,
+            access_log_rotate_size: 10 * 1024 * 1024,
+            metrics_port: None,
+            enable_upnp: true,
+            upnp_protocol: UpnpProtocol::Tcp,
+            upnp_lease_secs: 120,
+            upnp_max_retries: 5,
+            upnp_retry_base_secs: 5,
+            mdns: false,
+            persist_links: false,
+            default_ttl_secs: None,
+            default_download_limit: None,
+            default_password: None,
+            exit_when_done: false,
+            auth: None,
+            tls: None,
+            cors_allow_origin: None,
+            admin_token: None,
+            landing_show_links: false,
+            allow_upload: false,
+            upload_dir: PathBuf::from("uploads"),
+            max_upload_size: 1024 * 1024 * 1024,
+            max_connections: NonZeroUsize::new(1024).unwrap(),
+            header_timeout_secs: 30,
+            idle_timeout_secs: 120,
+            idle_shutdown_secs: None,
+            file_buffer_size: FILE_BUF_SIZE,
+            zero_copy: false,
+            tar_buffer_size: TAR_BUF_SIZE,
+        }
+    }
+}
+
+impl DirectShareConfig {
+    /// Minimum non zero uPnP lease accepted by most routers
+    const MIN_UPNP_LEASE_SECS: u32 = 30;
+
+    const MAX_UPNP_RETRIES: u32 = 100;
+
+    const MIN_BUFFER_SIZE: usize = 1024;
+
+    /// Path of `path` under `path_prefix` without leading slash
+    pub fn prefixed(&self, path: &str) -> String {
+        match self.path_prefix {
+            Some(ref prefix) => format!("{prefix}/{path}"),
+            None => path.to_string(),
+        }
+    }
+
+    /// Clamp nonsensical values
+    pub fn validated(mut self) -> Self {
+        if self.upnp_lease_secs != 0 && self.upnp_lease_secs < Self::MIN_UPNP_LEASE_SECS {
+            log::warn!(
+                "upnp_lease_secs: {} is too short, using {} instead",
+                self.upnp_lease_secs,
+                Self::MIN_UPNP_LEASE_SECS
+            );
+            self.upnp_lease_secs = Self::MIN_UPNP_LEASE_SECS;
+        }
+
+        if self.upnp_max_retries > Self::MAX_UPNP_RETRIES {
+            log::warn!(
+                "upnp_max_retries: {} is too large, using {} instead",
+                self.upnp_max_retries,
+                Self::MAX_UPNP_RETRIES
+            );
+            self.upnp_max_retries = Self::MAX_UPNP_RETRIES;
+        }
+
+        if self.upnp_retry_base_secs == 0 {
+            log::warn!("upnp_retry_base_secs cannot be 0, using 1 instead");
+            self.upnp_retry_base_secs = 1;
+        }
+
+        if !(1..=9).contains(&self.archive_gzip_level) {
+            let level = self.archive_gzip_level.clamp(1, 9);
+            log::warn!(
+                "archive_gzip_level: {} is out of range, using {level} instead",
+                self.archive_gzip_level
+            );
+            self.archive_gzip_level = level;
+        }
+
+        self.exclude
+            .retain(|pattern| match glob::Pattern::new(pattern) {
+                Ok(_) => true,
+                Err(err) => {
+                    log::warn!("ignoring invalid exclude pattern: {pattern} err: {err}");
+                    false
+                }
+            });
+
+        if self.file_buffer_size < Self::MIN_BUFFER_SIZE {
+            log::warn!(
+                "file_buffer_size: {} is too small, using {} instead",
+                self.file_buffer_size,
+                Self::MIN_BUFFER_SIZE
+            );
+            self.file_buffer_size = Self::MIN_BUFFER_SIZE;
+        }
+
+        if self.tar_buffer_size < Self::MIN_BUFFER_SIZE {
+            log::warn!(
+                "tar_buffer_size: {} is too small, using {} instead",
+                self.tar_buffer_size,
+                Self::MIN_BUFFER_SIZE
+            );
+            self.tar_buffer_size = Self::MIN_BUFFER_SIZE;
+        }
+
+        if let Some(ref prefix) = self.path_prefix {
+            let trimmed = prefix.trim_matches('/');
+            self.path_prefix = (!trimmed.is_empty()).then(|| trimmed.to_string());
+        }
+
+        self.disposition = self
+            .disposition
+            .into_iter()
+            .map(|(name, disposition)| {
+                (
+                    name.trim_start_matches('.').to_ascii_lowercase(),
+                    disposition,
+                )
+            })
+            .collect();
+
+        if self.rate_limit_window_secs == 0 {
+            log::warn!("rate_limit_window_secs cannot be 0, using 1 instead");
+            self.rate_limit_window_secs = 1;
+        }
+
+        self
+    }
+}
+
+/// Resolve config file path.
+/// Uses `explicit` if given, then config file in working directory if exists,
+/// then config file in platform config directory.
+pub fn config_path(explicit: Option<&Path>) -> PathBuf {
+    if let Some(path) = explicit {
+        return path.to_path_buf();
+    }
+
+    let local = PathBuf::from(CONFIG_FILE);
+    if local.exists() {
+        return local;
+    }
+
+    match dirs::config_dir() {
+        Some(dir) => dir.join(CONFIG_DIR).join(CONFIG_FILE),
+        None => local,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Tls certificate config
+pub struct TlsConfig {
+    /// PEM encoded certificate chain file
+    pub cert: PathBuf,
+
+    /// PEM encoded private key file
+    pub key: PathBuf,
+}
+
+/// Parse bind address, falls back to default address if malformed
+fn deserialize_bind_address<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<IpAddr, D::Error> {
+    let value = String::deserialize(deserializer)?;
+
+    Ok(value.parse().unwrap_or_else(|err| {
+        let default = DirectShareConfig::default().bind_address;
+        log::warn!("invalid bind_address: {value}, using {default} instead err: {err}");
+
+        default
+    }))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DirectoryMode {
+    /// Download whole directory as tarball archive
+    Tar,
+
+    /// Browse directory entries on html page
+    Listing,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Favicon {
+    /// Embedded default icon
+    Icon,
+
+    /// Empty `204 No Content` response
+    Empty,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Disposition {
+    /// Display in browser if possible
+    Inline,
+
+    /// Download as file
+    Attachment,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveFormat {
+    Tar,
+    Zip,
+
+    /// Tar compressed with gzip
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// File extension of the archive
+    pub const fn extension(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Tar => "tar",
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::TarGz => "tar.gz",
         }
     }
+
+    /// Media type sent as `Content-Type` of the archive
+    pub const fn content_type(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Tar => "application/x-tar",
+            ArchiveFormat::Zip => "application/zip",
+            ArchiveFormat::TarGz => "application/gzip",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyStyle {
+    /// Random characters from `key_alphabet`
+    Random,
+
+    /// Random words joined by hyphens
+    Words,
+
+    /// Characters derived from hash of the path.
+    /// Same path always gets same key, so anyone knowing the path can find the key.
+    Hash,
+
+    /// Characters derived from hash of the file content, directories fall back to path hash.
+    /// Key reveals nothing about the path but changes with the content.
+    #[serde(rename = "content_hash")]
+    ContentHash,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyAlphabet {
+    /// Url safe characters
+    Full,
+
+    /// Url safe characters except visually ambiguous ones like `O`, `0`, `l`, `1`, `I`
+    Unambiguous,
+}
+
+impl KeyAlphabet {
+    /// Characters of the alphabet
+    pub const fn chars(&self) -> &'static [char] {
+        const FULL: [char; 64] = [
+            '_', '-', '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'a', 'b', 'c', 'd', 'e',
+            'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v',
+            'w', 'x', 'y', 'z', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M',
+            'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z',
+        ];
+
+        const UNAMBIGUOUS: [char; 58] = [
+            '_', '-', '2', '3', '4', '5', '6', '7', '8', '9', 'a', 'b', 'c', 'd', 'e', 'f', 'g',
+            'h', 'i', 'j', 'k', 'm', 'n', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z',
+            'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'J', 'K', 'L', 'M', 'N', 'P', 'Q', 'R', 'S',
+            'T', 'U', 'V', 'W', 'X', 'Y', 'Z',
+        ];
+
+        match self {
+            KeyAlphabet::Full => &FULL,
+            KeyAlphabet::Unambiguous => &UNAMBIGUOUS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpnpProtocol {
+    Tcp,
+    Udp,
+
+    /// Map both tcp and udp, required by some routers
+    Both,
+}
+
+impl UpnpProtocol {
+    /// Port mapping protocols to be added
+    pub const fn protocols(&self) -> &'static [PortMappingProtocol] {
+        match self {
+            UpnpProtocol::Tcp => &[PortMappingProtocol::TCP],
+            UpnpProtocol::Udp => &[PortMappingProtocol::UDP],
+            UpnpProtocol::Both => &[PortMappingProtocol::TCP, PortMappingProtocol::UDP],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{ArchiveFormat, DirectShareConfig};
+
+    #[test]
+    pub fn archive_format_test() {
+        assert_eq!(ArchiveFormat::Tar.content_type(), "application/x-tar");
+        assert_eq!(ArchiveFormat::Zip.content_type(), "application/zip");
+        assert_eq!(ArchiveFormat::TarGz.content_type(), "application/gzip");
+        assert_eq!(ArchiveFormat::TarGz.extension(), "tar.gz");
+    }
+
+    #[test]
+    pub fn validated_test() {
+        let config = DirectShareConfig {
+            upnp_lease_secs: 1,
+            upnp_max_retries: 1000,
+            upnp_retry_base_secs: 0,
+            file_buffer_size: 0,
+            ..Default::default()
+        }
+        .validated();
+
+        assert_eq!(config.upnp_lease_secs, 30);
+        assert_eq!(config.upnp_max_retries, 100);
+        assert_eq!(config.upnp_retry_base_secs, 1);
+        assert_eq!(config.file_buffer_size, 1024);
+
+        let config = DirectShareConfig {
+            upnp_lease_secs: 0,
+            ..Default::default()
+        }
+        .validated();
+
+        assert_eq!(config.upnp_lease_secs, 0);
+    }
 }
@@ -4,8 +4,27 @@
  * Copyright (c) storycraft. Licensed under the MIT Licence.
  */
 
+pub const CONFIG_DIR: &str = "direct-share";
 pub const CONFIG_FILE: &str = "direct_share.toml";
+pub const LINKS_FILE: &str = "direct_share_links.toml";
 pub const FALLBACK_FILENAME: &str = "unknown";
+pub const BUNDLE_NAME: &str = "bundle";
+pub const INDEX_FILE: &str = "index.html";
+pub const UPLOAD_PATH: &str = "upload";
+pub const ADMIN_PATH: &str = "admin";
+pub const FAVICON_PATH: &str = "favicon.ico";
+pub const IGNORE_FILE: &str = ".directshareignore";
+pub const CHECKSUM_SUFFIX: &str = ".sha256";
 
 pub const TAR_BUF_SIZE: usize = 65536;
 pub const FILE_BUF_SIZE: usize = 65536;
+
+/// Crate version with commit and date of the build
+pub const VERSION_INFO: &str = concat!(
+    env!("CARGO_PKG_VERSION"),
+    " (",
+    env!("DIRECT_SHARE_GIT_COMMIT"),
+    " ",
+    env!("DIRECT_SHARE_BUILD_DATE"),
+    ")"
+);
@@ -0,0 +1,101 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::{
+    collections::HashMap,
+    fs::Metadata,
+    io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::cache;
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    len: u64,
+    modified: Option<SystemTime>,
+    digest: [u8; 32],
+}
+
+#[derive(Debug, Default)]
+/// Sha256 digests of served files, computed lazily on first request
+pub struct DigestCache {
+    entries: Mutex<HashMap<PathBuf, Entry>>,
+}
+
+impl DigestCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sha256 digest of file at `path`.
+    /// Cached digest is reused while size and modified time in `meta` are unchanged
+    pub async fn get(&self, path: &Path, meta: &Metadata) -> io::Result<[u8; 32]> {
+        let len = meta.len();
+        let modified = meta.modified().ok();
+
+        if let Some(entry) = self.entries.lock().unwrap().get(path) {
+            if entry.len == len && entry.modified == modified {
+                return Ok(entry.digest);
+            }
+        }
+
+        let digest = cache::sha256_file(path).await?;
+        log::debug!("computed digest path: {} len: {len}", path.display());
+
+        self.entries.lock().unwrap().insert(
+            path.to_path_buf(),
+            Entry {
+                len,
+                modified,
+                digest,
+            },
+        );
+
+        Ok(digest)
+    }
+}
+
+/// Value for `Content-Digest` header
+pub fn content_digest(digest: &[u8; 32]) -> String {
+    format!("sha-256=:{}:", STANDARD.encode(digest))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::digest::{content_digest, DigestCache};
+
+    #[tokio::test]
+    pub async fn digest_cache_test() {
+        let path = std::env::temp_dir().join("direct_share_digest_cache_test.txt");
+        fs::write(&path, "hello world").unwrap();
+
+        let cache = DigestCache::new();
+        let digest = cache
+            .get(&path, &fs::metadata(&path).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(
+            content_digest(&digest),
+            "sha-256=:uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=:"
+        );
+
+        fs::write(&path, "hello world!").unwrap();
+        let changed = cache
+            .get(&path, &fs::metadata(&path).unwrap())
+            .await
+            .unwrap();
+        assert_ne!(digest, changed);
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,144 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::{collections::BTreeMap, path::Path};
+
+use hyper::header::{HeaderValue, InvalidHeaderValue};
+use mime_guess::Mime;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use crate::{config::Disposition, util::sanitize_filename};
+
+/// Characters to be escaped in RFC 5987 ext-value
+const ATTR_CHAR: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'!')
+    .remove(b'#')
+    .remove(b'$')
+    .remove(b'&')
+    .remove(b'+')
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'^')
+    .remove(b'_')
+    .remove(b'`')
+    .remove(b'|')
+    .remove(b'~');
+
+/// Create Content-Disposition header value.
+/// Filename is encoded as RFC 5987 `filename*` with quoted ASCII `filename` fallback for old clients.
+/// Filename is sanitized with [`sanitize_filename`].
+pub fn content_disposition(
+    inline: bool,
+    file_name: &str,
+) -> Result<HeaderValue, InvalidHeaderValue> {
+    let disposition = if inline { "inline" } else { "attachment" };
+    let file_name = sanitize_filename(file_name);
+
+    HeaderValue::from_str(&format!(
+        "{disposition}; filename=\"{}\"; filename*=UTF-8''{}",
+        ascii_fallback(&file_name),
+        utf8_percent_encode(&file_name, ATTR_CHAR)
+    ))
+}
+
+/// Check if file should be displayed inline by `rules` of extension, media type or top level type.
+/// Earlier ones take precedence.
+pub fn is_inline(
+    rules: &BTreeMap<String, Disposition>,
+    file_name: &str,
+    media_type: Option<&Mime>,
+) -> bool {
+    let extension = Path::new(file_name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+
+    let rule = extension
+        .and_then(|extension| rules.get(&extension))
+        .or_else(|| media_type.and_then(|media_type| rules.get(media_type.essence_str())))
+        .or_else(|| media_type.and_then(|media_type| rules.get(media_type.type_().as_str())));
+
+    rule == Some(&Disposition::Inline)
+}
+
+/// Replace characters not allowed in quoted string filename
+fn ascii_fallback(file_name: &str) -> String {
+    file_name
+        .chars()
+        .map(|ch| match ch {
+            '"' | '\\' => '_',
+            ' ' => ' ',
+            ch if ch.is_ascii_graphic() => ch,
+            _ => '_',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use percent_encoding::percent_decode_str;
+
+    use crate::{
+        config::{DirectShareConfig, Disposition},
+        disposition::{content_disposition, is_inline},
+    };
+
+    fn encoded_name(value: &str) -> String {
+        let (_, encoded) = value.split_once("filename*=UTF-8''").unwrap();
+
+        percent_decode_str(encoded)
+            .decode_utf8()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    pub fn is_inline_test() {
+        let mut rules = DirectShareConfig::default().disposition;
+        rules.insert("svg".into(), Disposition::Attachment);
+
+        let inline =
+            |name: &str| is_inline(&rules, name, mime_guess::from_path(name).first().as_ref());
+
+        assert!(inline("photo.JPG"));
+        assert!(inline("report.pdf"));
+        assert!(inline("notes.txt"));
+        assert!(!inline("drawing.svg"));
+        assert!(!inline("movie.mp4"));
+        assert!(!inline("archive.zip"));
+    }
+
+    #[test]
+    pub fn content_disposition_test() {
+        for name in [
+            "report.pdf",
+            "my file (1).txt",
+            "보고서 최종.docx",
+            "写真.jpg",
+            "🎉 party 🎂.png",
+            "quote\"name.txt",
+        ] {
+            let value = content_disposition(false, name).unwrap();
+            let value = value.to_str().unwrap();
+
+            assert!(value.starts_with("attachment; filename=\""));
+            assert_eq!(encoded_name(value), name);
+        }
+
+        let value = content_disposition(false, "back\\slash.txt").unwrap();
+        assert_eq!(encoded_name(value.to_str().unwrap()), "back_slash.txt");
+
+        assert_eq!(
+            content_disposition(false, "line\r\nbreak.txt").unwrap(),
+            "attachment; filename=\"linebreak.txt\"; filename*=UTF-8''linebreak.txt"
+        );
+
+        assert_eq!(
+            content_disposition(true, "写真 1.jpg").unwrap(),
+            "inline; filename=\"__ 1.jpg\"; filename*=UTF-8''%E5%86%99%E7%9C%9F%201.jpg"
+        );
+    }
+}
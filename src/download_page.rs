@@ -0,0 +1,59 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::fmt::Write;
+
+use crate::listing::{escape_html, format_size};
+
+/// Query parameter starting the download from the page
+pub const DOWNLOAD_PARAM: &str = "dl";
+
+/// Render page shown to browsers before downloading archive named `name`.
+/// `href` starts the download, `size` is estimated size of the archive if known.
+pub fn render(name: &str, size: Option<u64>, href: &str) -> String {
+    let name = escape_html(name);
+
+    let mut page = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{name}</title>\n</head>\n<body>\n<h1>{name}</h1>\n"
+    );
+
+    if let Some(size) = size {
+        let _ = writeln!(page, "<p>Estimated size: {}</p>", format_size(size));
+    }
+
+    let _ = writeln!(
+        page,
+        "<p>The archive is generated while downloading, so progress may not be shown.</p>\n<p><a href=\"{}\">Click to download</a></p>",
+        escape_html(href)
+    );
+
+    page.push_str("</body>\n</html>\n");
+
+    page
+}
+
+/// Href of download link keeping other parameters of `query`
+pub fn download_href(query: Option<&str>) -> String {
+    match query {
+        Some(query) if !query.is_empty() => format!("?{query}&{DOWNLOAD_PARAM}=1"),
+        _ => format!("?{DOWNLOAD_PARAM}=1"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::download_page::{download_href, render};
+
+    #[test]
+    pub fn render_test() {
+        let page = render("<docs>.tar", Some(2048), &download_href(Some("pw=a")));
+        assert!(page.contains("<h1>&lt;docs&gt;.tar</h1>"));
+        assert!(page.contains("2.0 KiB"));
+        assert!(page.contains("href=\"?pw=a&amp;dl=1\""));
+
+        assert_eq!(download_href(None), "?dl=1");
+    }
+}
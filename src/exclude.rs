@@ -0,0 +1,119 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::path::Path;
+
+use glob::{MatchOptions, Pattern};
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+#[derive(Debug, Clone, Default)]
+/// Gitignore style glob patterns excluding paths from directory archives.
+/// Patterns without `/` match names at any depth, others match paths relative to the shared directory.
+/// Trailing `/` matches directories only.
+pub struct ExcludeRules {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    /// Pattern as written
+    source: String,
+
+    pattern: Pattern,
+
+    /// Match whole relative path instead of the name
+    anchored: bool,
+
+    dir_only: bool,
+}
+
+impl ExcludeRules {
+    /// Parse patterns. Blank lines and lines starting with `#` are ignored
+    pub fn new<'a>(patterns: impl IntoIterator<Item = &'a str>) -> Self {
+        let rules = patterns
+            .into_iter()
+            .filter_map(|line| {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    return None;
+                }
+
+                let (glob, dir_only) = match line.strip_suffix('/') {
+                    Some(glob) => (glob, true),
+                    None => (line, false),
+                };
+                let anchored = glob.contains('/');
+
+                match Pattern::new(glob.trim_start_matches('/')) {
+                    Ok(pattern) => Some(Rule {
+                        source: line.to_string(),
+                        pattern,
+                        anchored,
+                        dir_only,
+                    }),
+                    Err(err) => {
+                        log::warn!("invalid exclude pattern: {line} err: {err}");
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        Self { rules }
+    }
+
+    /// Add rules of `other`
+    pub fn extend(&mut self, other: ExcludeRules) {
+        self.rules.extend(other.rules);
+    }
+
+    /// Patterns as written
+    pub fn patterns(&self) -> impl Iterator<Item = &str> {
+        self.rules.iter().map(|rule| rule.source.as_str())
+    }
+
+    /// Check if entry at `relative` path is excluded
+    pub fn is_excluded(&self, relative: &Path, is_dir: bool) -> bool {
+        let name = relative.file_name().map(Path::new).unwrap_or(relative);
+
+        self.rules.iter().any(|rule| {
+            if rule.dir_only && !is_dir {
+                return false;
+            }
+
+            let target = if rule.anchored { relative } else { name };
+            rule.pattern.matches_path_with(target, MATCH_OPTIONS)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::exclude::ExcludeRules;
+
+    #[test]
+    pub fn is_excluded_test() {
+        let rules = ExcludeRules::new(
+            "# build output\nnode_modules\ntarget/\n*.log\n/docs/*.pdf\n\n".lines(),
+        );
+
+        assert!(rules.is_excluded(Path::new("node_modules"), true));
+        assert!(rules.is_excluded(Path::new("web/node_modules"), true));
+        assert!(rules.is_excluded(Path::new("target"), true));
+        assert!(!rules.is_excluded(Path::new("target"), false));
+        assert!(rules.is_excluded(Path::new("logs/app.log"), false));
+        assert!(rules.is_excluded(Path::new("docs/manual.pdf"), false));
+        assert!(!rules.is_excluded(Path::new("docs/old/manual.pdf"), false));
+        assert!(!rules.is_excluded(Path::new("src/main.rs"), false));
+    }
+}
@@ -0,0 +1,98 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::net::{IpAddr, SocketAddr};
+
+use hyper::{header, HeaderMap};
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// Client ip reported by reverse proxy in `Forwarded` or `X-Forwarded-For` header.
+/// Last entry is used since it is the one appended by the nearest proxy.
+pub fn client_ip(headers: &HeaderMap) -> Option<IpAddr> {
+    if let Some(value) = last_value(headers, header::FORWARDED.as_str()) {
+        return forwarded_for(&value);
+    }
+
+    parse_node(&last_value(headers, X_FORWARDED_FOR)?)
+}
+
+/// Last comma separated element across every header line of the name
+fn last_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(name)
+        .iter()
+        .next_back()?
+        .to_str()
+        .ok()?
+        .rsplit(',')
+        .next()
+        .map(str::to_string)
+}
+
+/// Parse `for` parameter of `Forwarded` element
+fn forwarded_for(element: &str) -> Option<IpAddr> {
+    element.split(';').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+
+        if key.trim().eq_ignore_ascii_case("for") {
+            parse_node(value.trim().trim_matches('"'))
+        } else {
+            None
+        }
+    })
+}
+
+/// Parse node which is ip address with optional port. IPv6 address can be bracketed.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let node = node.trim();
+
+    if let Ok(ip) = node.parse() {
+        return Some(ip);
+    }
+
+    if let Ok(addr) = node.parse::<SocketAddr>() {
+        return Some(addr.ip());
+    }
+
+    node.strip_prefix('[')?.strip_suffix(']')?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    use hyper::{header::HeaderValue, HeaderMap};
+
+    use crate::forwarded::client_ip;
+
+    #[test]
+    pub fn client_ip_test() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(client_ip(&headers), None);
+
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("10.0.0.1, 203.0.113.7"),
+        );
+        assert_eq!(
+            client_ip(&headers),
+            Some(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 7)))
+        );
+
+        headers.insert(
+            "forwarded",
+            HeaderValue::from_static("for=10.0.0.1, for=\"[2001:db8::1]:4711\";proto=https"),
+        );
+        assert_eq!(
+            client_ip(&headers),
+            Some(IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)))
+        );
+
+        headers.insert("forwarded", HeaderValue::from_static("for=unknown"));
+        assert_eq!(client_ip(&headers), None);
+    }
+}
@@ -0,0 +1,56 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::fmt::Write;
+
+use crate::listing::escape_html;
+
+/// Render landing page served at the root.
+/// `links` are pairs of url path and name, shown only if given.
+pub fn render(count: usize, links: Option<&[(String, String)]>) -> String {
+    let mut page = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>DirectShare</title>\n</head>\n<body>\n<h1>DirectShare</h1>\n",
+    );
+
+    let _ = writeln!(
+        page,
+        "<p>{count} {} shared. Ask the sender for the link.</p>",
+        if count == 1 { "link is" } else { "links are" }
+    );
+
+    if let Some(links) = links {
+        page.push_str("<ul>\n");
+        for (href, name) in links {
+            let _ = writeln!(
+                page,
+                "<li><a href=\"{}\">{}</a></li>",
+                escape_html(href),
+                escape_html(name)
+            );
+        }
+        page.push_str("</ul>\n");
+    }
+
+    page.push_str("</body>\n</html>\n");
+
+    page
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::landing::render;
+
+    #[test]
+    pub fn render_test() {
+        let page = render(2, None);
+        assert!(page.contains("2 links are shared"));
+        assert!(!page.contains("<a"));
+
+        let links = [("/abc".to_string(), "<file>".to_string())];
+        let page = render(1, Some(&links));
+        assert!(page.contains("<a href=\"/abc\">&lt;file&gt;</a>"));
+    }
+}
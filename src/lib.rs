@@ -0,0 +1,43 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+pub mod access_log;
+pub mod activity;
+pub mod admin;
+pub mod app;
+pub mod archive;
+pub mod archive_cache;
+pub mod auth;
+pub mod cache;
+pub mod cli;
+pub mod config;
+pub mod constants;
+pub mod digest;
+pub mod disposition;
+pub mod download_page;
+pub mod exclude;
+pub mod forwarded;
+pub mod landing;
+pub mod listener;
+pub mod listing;
+pub mod map;
+pub mod mdns;
+pub mod media;
+pub mod metrics;
+pub mod pause;
+pub mod range;
+pub mod request_limit;
+pub mod sendto;
+pub mod sized;
+pub mod throttle;
+pub mod timeout;
+pub mod tls;
+pub mod transfer;
+pub mod upload;
+pub mod util;
+pub mod zero_copy;
+
+pub use app::{DirectShare, DirectShareBuilder};
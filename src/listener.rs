@@ -0,0 +1,94 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::{future::Future, io, net::SocketAddr};
+
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpListener,
+};
+
+#[cfg(not(target_os = "linux"))]
+use tokio::net::TcpStream;
+
+use crate::zero_copy::SendFileSlot;
+
+#[cfg(target_os = "linux")]
+use crate::zero_copy::ZeroCopyStream;
+
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+
+#[derive(Debug)]
+/// Listener accepting connections for server
+pub enum Listener {
+    Tcp(TcpListener),
+
+    #[cfg(unix)]
+    Unix(UnixListener),
+}
+
+impl From<TcpListener> for Listener {
+    fn from(listener: TcpListener) -> Self {
+        Self::Tcp(listener)
+    }
+}
+
+#[cfg(unix)]
+impl From<UnixListener> for Listener {
+    fn from(listener: UnixListener) -> Self {
+        Self::Unix(listener)
+    }
+}
+
+/// Source of connections
+pub(crate) trait Accept {
+    type Stream: AsyncRead + AsyncWrite + Unpin + Send + 'static;
+
+    /// Accept connection and its peer address
+    fn accept(&self) -> impl Future<Output = io::Result<(Self::Stream, SocketAddr)>> + Send;
+
+    /// Slot for sending files of the connection with zero copy, `None` if not supported
+    fn send_file(_stream: &Self::Stream) -> Option<SendFileSlot> {
+        None
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Accept for TcpListener {
+    type Stream = ZeroCopyStream;
+
+    async fn accept(&self) -> io::Result<(Self::Stream, SocketAddr)> {
+        let (stream, addr) = TcpListener::accept(self).await?;
+
+        Ok((ZeroCopyStream::new(stream), addr))
+    }
+
+    fn send_file(stream: &Self::Stream) -> Option<SendFileSlot> {
+        Some(stream.slot())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl Accept for TcpListener {
+    type Stream = TcpStream;
+
+    fn accept(&self) -> impl Future<Output = io::Result<(Self::Stream, SocketAddr)>> + Send {
+        TcpListener::accept(self)
+    }
+}
+
+#[cfg(unix)]
+impl Accept for UnixListener {
+    type Stream = UnixStream;
+
+    /// Unix socket peers have no ip address, reported as localhost
+    async fn accept(&self) -> io::Result<(Self::Stream, SocketAddr)> {
+        let (stream, _) = UnixListener::accept(self).await?;
+
+        Ok((stream, SocketAddr::from(([127, 0, 0, 1], 0))))
+    }
+}
@@ -0,0 +1,198 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::{
+    fmt::Write,
+    io,
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
+};
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use tokio::fs;
+
+/// Characters to be escaped in url path segment
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+#[derive(Debug)]
+struct ListingEntry {
+    name: String,
+    is_dir: bool,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+/// Resolve percent encoded sub path requested under shared directory.
+/// Returns `None` if the path is invalid or tries to escape the directory.
+pub fn resolve_sub_path(root: &Path, sub_path: &str) -> Option<PathBuf> {
+    let decoded = percent_decode_str(sub_path).decode_utf8().ok()?;
+
+    let mut path = root.to_path_buf();
+    for component in Path::new(decoded.as_ref()).components() {
+        match component {
+            Component::Normal(name) => path.push(name),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+
+    Some(path)
+}
+
+/// Check if `path` stays inside `root` after resolving symlinks
+pub async fn is_contained(root: &Path, path: &Path) -> bool {
+    match (fs::canonicalize(root).await, fs::canonicalize(path).await) {
+        (Ok(root), Ok(path)) => path.starts_with(root),
+        _ => false,
+    }
+}
+
+/// Percent encode relative path to be used in url
+pub fn encode_path(path: &Path) -> String {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => {
+                Some(utf8_percent_encode(&name.to_string_lossy(), SEGMENT).to_string())
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Render html listing page of directory.
+/// Entries are linked under `base` url path.
+pub async fn render(dir: &Path, title: &str, base: &str, has_parent: bool) -> io::Result<String> {
+    let mut entries = Vec::new();
+
+    let mut read_dir = fs::read_dir(dir).await?;
+    while let Some(entry) = read_dir.next_entry().await? {
+        // Follow symlinks, fallback to the link itself if it is broken
+        let meta = match fs::metadata(entry.path()).await {
+            Ok(meta) => meta,
+            Err(_) => entry.metadata().await?,
+        };
+
+        entries.push(ListingEntry {
+            name: entry.file_name().to_string_lossy().to_string(),
+            is_dir: meta.is_dir(),
+            size: meta.len(),
+            modified: meta.modified().ok(),
+        });
+    }
+
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+
+    let title = escape_html(title);
+    let mut page = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n<body>\n<h1>{title}</h1>\n<table>\n<tr><th>Name</th><th>Size</th><th>Modified</th></tr>\n"
+    );
+
+    if has_parent {
+        if let Some((parent, _)) = base.rsplit_once('/') {
+            let _ = writeln!(
+                page,
+                "<tr><td><a href=\"{}/\">../</a></td><td></td><td></td></tr>",
+                escape_html(parent)
+            );
+        }
+    }
+
+    for entry in entries {
+        let href = format!(
+            "{base}/{}{}",
+            utf8_percent_encode(&entry.name, SEGMENT),
+            if entry.is_dir { "/" } else { "" }
+        );
+
+        let name = escape_html(&entry.name);
+        let size = if entry.is_dir {
+            "-".to_string()
+        } else {
+            format_size(entry.size)
+        };
+        let modified = entry
+            .modified
+            .map(httpdate::fmt_http_date)
+            .unwrap_or_default();
+
+        let _ = writeln!(
+            page,
+            "<tr><td><a href=\"{}\">{name}{}</a></td><td>{size}</td><td>{modified}</td></tr>",
+            escape_html(&href),
+            if entry.is_dir { "/" } else { "" }
+        );
+    }
+
+    page.push_str("</table>\n</body>\n</html>\n");
+
+    Ok(page)
+}
+
+pub fn format_size(size: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{size} {}", UNITS[0])
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::listing::resolve_sub_path;
+
+    #[test]
+    pub fn resolve_sub_path_test() {
+        let root = Path::new("/shared");
+
+        assert_eq!(
+            resolve_sub_path(root, "a/b%20c.txt").unwrap(),
+            Path::new("/shared/a/b c.txt")
+        );
+        assert_eq!(resolve_sub_path(root, "").unwrap(), root);
+        assert!(resolve_sub_path(root, "../etc/passwd").is_none());
+        assert!(resolve_sub_path(root, "a/%2E%2E/%2E%2E/etc").is_none());
+    }
+}
@@ -4,336 +4,782 @@
  * Copyright (c) storycraft. Licensed under the MIT Licence.
  */
 
-pub mod config;
-pub mod constants;
-pub mod map;
-
 use std::{
-    convert::Infallible,
     env,
     error::Error,
-    ffi::OsString,
-    fs::Metadata,
+    future::pending,
     io::{self, ErrorKind},
-    net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4},
-    num::NonZeroU16,
-    path::Path,
-    sync::Arc,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4},
+    path::{self, Path, PathBuf},
+    sync::Mutex,
     time::Duration,
 };
 
-use config::DirectShareConfig;
-use constants::{FILE_BUF_SIZE, TAR_BUF_SIZE};
-use futures_util::{FutureExt, TryStreamExt};
-use http_body_util::{combinators::BoxBody, BodyExt, Empty, StreamBody};
-use hyper::{
-    body::{Bytes, Frame},
-    header,
-    server::conn::http1,
-    service::service_fn,
-    Method, Request, Response, StatusCode,
+use arboard::Clipboard;
+use clap::Parser;
+#[cfg(unix)]
+use direct_share::pause::Pause;
+use direct_share::{
+    app, cli,
+    config::{self, DirectShareConfig},
+    constants,
+    listener::Listener,
+    map::{self, PathMap, Registration},
+    mdns, sendto, tls, DirectShare,
 };
-use hyper_util::rt::TokioIo;
 use igd::{aio::search_gateway, PortMappingProtocol, SearchOptions};
-use local_ip_address::local_ip;
+use local_ip_address::{local_ip, local_ipv6};
 use log::LevelFilter;
-use never_say_never::Never;
+use qrcode::{render::unicode::Dense1x2, QrCode};
+use serde::Serialize;
 use thiserror::Error;
-use tokio::{
-    fs::{self, File},
-    io::duplex,
-    net::TcpListener,
-    select, signal, spawn,
-    time::sleep,
-};
-use tokio_util::io::ReaderStream;
+#[cfg(unix)]
+use tokio::net::UnixListener;
+use tokio::{fs, io::AsyncReadExt, net::TcpListener, select, signal, spawn, time::sleep};
+use tokio_util::sync::CancellationToken;
 
-use crate::map::PathMap;
+/// Log level used if neither RUST_LOG nor `log_level` is set
+#[cfg(not(debug_assertions))]
+const DEFAULT_LOG_LEVEL: LevelFilter = LevelFilter::Info;
+#[cfg(debug_assertions)]
+const DEFAULT_LOG_LEVEL: LevelFilter = LevelFilter::Trace;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
+    // Level is lowered after init so config can raise it again
+    let env_level = env::var_os("RUST_LOG").is_some();
     pretty_env_logger::formatted_timed_builder()
-        .filter_level({
-            #[cfg(not(debug_assertions))]
-            {
-                LevelFilter::Info
-            }
-            #[cfg(debug_assertions)]
-            {
-                LevelFilter::Trace
-            }
-        })
+        .filter_level(LevelFilter::Trace)
         .parse_default_env()
         .init();
+    if !env_level {
+        log::set_max_level(DEFAULT_LOG_LEVEL);
+    }
+
+    let args = cli::Args::parse();
+
+    if args.install_sendto {
+        match sendto::install() {
+            Ok(path) => log::info!("installed send to shortcut path: {}", path.display()),
+            Err(err) => log::error!("cannot install send to shortcut err: {err}"),
+        }
+
+        return Ok(());
+    }
 
     log::info!("initializing DirectShare...");
 
-    let config = load_config().await;
+    let mut config = load_config(&config::config_path(args.config.as_deref())).await;
+    // Overrides are validated together with the config
+    args.apply(&mut config);
+    let mut config = config.validated();
+
+    // RUST_LOG takes precedence over config
+    if let (false, Some(level)) = (env_level, &config.log_level) {
+        match level.parse::<LevelFilter>() {
+            Ok(level) => log::set_max_level(level),
+            Err(_) => {
+                log::warn!("unknown log_level: {level}, using default level: {DEFAULT_LOG_LEVEL}")
+            }
+        }
+    }
+
+    let paths = match args.collect_paths() {
+        Ok(paths) => paths,
+        Err(err) => {
+            log::error!("cannot read paths from stdin err: {err}");
+            return Ok(());
+        }
+    };
+
+    // Size of piped content is unknown until stdin is closed, buffer it to send Content-Length
+    let piped = if args.pipes_content() {
+        let mut data = Vec::new();
+        if let Err(err) = tokio::io::stdin().read_to_end(&mut data).await {
+            log::error!("cannot read content from stdin err: {err}");
+            return Ok(());
+        }
+
+        Some(data)
+    } else {
+        None
+    };
+
+    // Refuse missing paths instead of serving links that always fail
+    let paths: Vec<_> = paths
+        .into_iter()
+        .filter(|path| match std::fs::metadata(path) {
+            Ok(_) => true,
+            Err(err) => {
+                log::error!("cannot register path: {} err: {err}", path.display());
+                false
+            }
+        })
+        .collect();
+
+    let map = if config.persist_links {
+        match PathMap::persistent(config.key_length, constants::LINKS_FILE.into()) {
+            Ok(map) => map
+                .with_style(config.key_style)
+                .with_alphabet(config.key_alphabet)
+                .with_case_insensitive(config.case_insensitive_keys),
+            Err(err) => {
+                log::error!("cannot load saved links err: {err}");
+                return Ok(());
+            }
+        }
+    } else {
+        PathMap::new(config.key_length)
+            .with_style(config.key_style)
+            .with_alphabet(config.key_alphabet)
+            .with_case_insensitive(config.case_insensitive_keys)
+    };
+
+    // Links registered on dry run are thrown away
+    let map = if args.dry_run {
+        map.without_file()
+    } else {
+        map
+    };
+
+    let named = args.named_paths();
 
-    let args: Vec<OsString> = env::args_os().skip(1).collect();
-    if args.is_empty() {
+    if paths.is_empty() && named.is_empty() && piped.is_none() && map.is_empty() {
         log::error!("please drag files to start server");
         return Ok(());
     }
 
-    let mut map = PathMap::new(config.key_length);
+    // Nothing is bound on dry run, urls use port in config
+    let listener = if args.dry_run {
+        None
+    } else {
+        let listener = match config.unix_socket {
+            Some(ref path) => bind_unix(path),
+            None => bind_listener(&mut config).map(Listener::from),
+        };
 
-    let ip = local_ip().unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        match listener {
+            Ok(listener) => Some(listener),
+            Err(err) => {
+                log::error!("cannot start server err: {err}");
+                return Ok(());
+            }
+        }
+    };
 
-    let service = spawn(upnp_service(ip, config.port));
+    // Address links are served on, `None` when listening on unix socket
+    let host = config
+        .unix_socket
+        .is_none()
+        .then(|| SocketAddr::new(advertised_ip(config.bind_address), config.port.get()));
+    let hostname = host.and_then(|_| local_hostname());
+
+    let acceptor = config
+        .tls
+        .as_ref()
+        .and_then(|tls| match tls::load_acceptor(tls) {
+            Ok(acceptor) => Some(acceptor),
+            Err(err) => {
+                log::error!("cannot load tls certificate, falling back to http err: {err}");
+                None
+            }
+        });
 
-    for arg in args {
-        let key = map.register(arg.clone().into());
+    if !map.is_empty() {
+        log::info!("restored {} saved links", map.len());
+    }
 
-        log::info!(
-            "registered {} url: http://{ip}:{}/{key}",
-            arg.to_string_lossy(),
-            config.port
-        );
+    let mut builder = DirectShare::builder(config).map(map);
+    if let Some(acceptor) = acceptor {
+        builder = builder.tls(acceptor);
     }
+    let share = builder.build();
 
-    log::info!("server starting on http://{}:{}/", ip, config.port);
-    let listener = match TcpListener::bind(SocketAddrV4::new(
-        Ipv4Addr::UNSPECIFIED,
-        config.port.get(),
-    ))
-    .await
-    {
-        Ok(listener) => listener,
-        Err(err) => {
-            log::error!("cannot start server err: {err}");
-            return Ok(());
+    let config = share.config().clone();
+    let scheme = share.scheme();
+
+    let shutdown = CancellationToken::new();
+    spawn({
+        let shutdown = shutdown.clone();
+
+        async move {
+            if let Err(err) = shutdown_signal().await {
+                log::warn!("shutdown signal hook failed err: {err}");
+                return;
+            }
+
+            log::info!("stopping server...");
+            shutdown.cancel();
+        }
+    });
+
+    #[cfg(unix)]
+    spawn({
+        let pause = share.pause().clone();
+
+        async move {
+            if let Err(err) = pause_signal(&pause).await {
+                log::warn!("pause signal hook failed err: {err}");
+            }
         }
+    });
+
+    if let Some(secs) = config.idle_shutdown_secs {
+        let share = share.clone();
+        let shutdown = shutdown.clone();
+
+        spawn(async move {
+            share.wait_idle(Duration::from_secs(secs)).await;
+            log::info!("no activity for {secs} secs, stopping server...");
+            shutdown.cancel();
+        });
+    }
+
+    let service = match host {
+        _ if args.dry_run => None,
+
+        Some(host) if config.enable_upnp => Some(spawn(upnp_service(
+            host.ip(),
+            share.clone(),
+            shutdown.clone(),
+        ))),
+
+        Some(_) => {
+            log::info!("uPnP disabled, links are reachable on local network only");
+            None
+        }
+
+        None => None,
     };
 
-    select! {
-        Ok(_) = signal::ctrl_c() => {
-            log::info!("stopping server...");
-            let _ = service.await;
+    let mdns = host.filter(|_| config.mdns && !args.dry_run).map(|host| {
+        spawn(mdns::mdns_service(
+            host.ip(),
+            host.port(),
+            scheme,
+            shutdown.clone(),
+        ))
+    });
+
+    // Path only url on unix socket, public address is decided by the proxy
+    let url_of = |key: &str| match (host, &config.public_host) {
+        (Some(host), Some(public_host)) => {
+            map::public_url(scheme, public_host, host.port(), &config.prefixed(key))
         }
-        _ = server(listener, Arc::new(map)) => {}
+        (Some(host), None) => map::link_url(scheme, host, &config.prefixed(key)),
+        (None, _) => format!("/{}", config.prefixed(key)),
     };
 
-    Ok(())
-}
+    // Name, key, kind and shared path of each link
+    let mut links: Vec<(String, String, String, Option<PathBuf>)> =
+        if config.bundle && !paths.is_empty() {
+            let name = format!("bundle of {} paths", paths.len());
+            let paths = paths
+                .into_iter()
+                .map(|path| path::absolute(&path).unwrap_or(path))
+                .collect();
+
+            vec![(name, share.register_bundle(paths), "bundle".into(), None)]
+        } else {
+            paths
+                .into_iter()
+                .filter_map(|path| {
+                    let saved = if config.persist_links {
+                        // Saved links should not depend on working directory or symlinks
+                        let path = map::resolve_path(&path).unwrap_or(path.clone());
+
+                        share
+                            .map()
+                            .read()
+                            .unwrap()
+                            .find_key(&path)
+                            .map(str::to_string)
+                    } else {
+                        None
+                    };
+
+                    let registration = match saved {
+                        Some(key) => Registration::new(key, &path),
+                        None => share.register_info(&path),
+                    };
+
+                    match registration {
+                        Ok(registration) => Some((
+                            registration.to_string(),
+                            registration.key.clone(),
+                            registration.kind.to_string(),
+                            Some(registration.path),
+                        )),
+                        Err(err) => {
+                            log::error!("cannot register path: {} err: {err}", path.display());
+                            None
+                        }
+                    }
+                })
+                .collect()
+        };
 
-async fn server(listener: TcpListener, map: Arc<PathMap>) -> Result<Never, anyhow::Error> {
-    loop {
-        let (stream, addr) = listener.accept().await?;
+    for (key, path) in named {
+        match share.register_with_key(key.clone(), &path) {
+            Ok(registration) => links.push((
+                registration.to_string(),
+                registration.key.clone(),
+                registration.kind.to_string(),
+                Some(registration.path),
+            )),
+            Err(err) => log::error!(
+                "cannot register path: {} key: {key} err: {err}",
+                path.display()
+            ),
+        }
+    }
 
-        log::trace!("{addr} connected");
+    if let Some(data) = piped {
+        let name = format!("{} ({} bytes from stdin)", args.stdin_name, data.len());
+        let key = share.register_bytes(args.stdin_name.clone(), data);
+        links.push((name, key, "stdin".into(), None));
+    }
 
-        spawn({
-            let map = map.clone();
+    let mut urls = Vec::with_capacity(links.len());
+    let mut summaries = Vec::new();
+    for (name, key, kind, path) in links {
+        let url = url_of(&key);
+        match (host, &config.public_host) {
+            (Some(_), Some(_)) => log::info!("registered {name} public url: {url}"),
+            (Some(_), None) => log::info!("registered {name} lan url: {url}"),
+            (None, _) => log::info!("registered {name} url: {url}"),
+        }
+        if let Some(ref hostname) = hostname {
+            log::info!(
+                "alternate url: {scheme}://{hostname}.local:{}/{}",
+                config.port,
+                config.prefixed(&key)
+            );
+        }
 
-            async move {
-                if let Err(err) = http1::Builder::new()
-                    .serve_connection(
-                        TokioIo::new(stream),
-                        service_fn(|req| response(addr, &map, req).map(Ok::<_, Infallible>)),
-                    )
-                    .await
-                {
-                    log::warn!("could not deliver file from addr: {addr} err: {err}");
+        // Stdout is reserved for json output, path only url cannot be scanned
+        if config.show_qr && !args.print_json && host.is_some() {
+            print_qr(&url);
+        }
+
+        if args.print_json {
+            summaries.push(LinkSummary {
+                kind,
+                path,
+                key,
+                url: url.clone(),
+                ip: host.map(|host| host.ip()),
+                bind_address: host.map(|_| config.bind_address),
+                port: host.map(|host| host.port()),
+                unix_socket: config.unix_socket.clone(),
+            });
+        }
+
+        urls.push(url);
+    }
+
+    if args.print_json {
+        println!("{}", serde_json::to_string(&summaries)?);
+    }
+
+    let Some(listener) = listener else {
+        log::info!("dry run finished, server is not started");
+        return Ok(());
+    };
+
+    // Clipboard content is served by the instance on some platforms, keep it until exit
+    let _clipboard = (config.copy_to_clipboard && !urls.is_empty())
+        .then(|| copy_to_clipboard(&urls.join("\n")))
+        .flatten();
+
+    if config.open_browser {
+        match urls.first() {
+            Some(url) if host.is_some() => {
+                if let Err(err) = webbrowser::open(url) {
+                    log::warn!("cannot open browser url: {url} err: {err}");
                 }
             }
-        });
+
+            Some(_) => log::warn!("cannot open browser on unix socket"),
+
+            None => {}
+        }
     }
-}
 
-async fn upnp_service(ip: IpAddr, port: NonZeroU16) {
-    let gateway = match search_gateway(SearchOptions::default()).await {
-        Ok(gateway) => gateway,
-        Err(err) => {
-            log::warn!("uPnP discovery failed err: {err}");
-            return;
+    if let Some(secs) = config.default_ttl_secs {
+        log::info!("links expire after {secs} secs");
+    }
+
+    if let Some(limit) = config.default_download_limit {
+        log::info!("links can be downloaded {limit} times");
+    }
+
+    if config.allow_upload {
+        log::info!(
+            "uploads enabled url: {} dir: {}",
+            url_of(constants::UPLOAD_PATH),
+            config.upload_dir.display()
+        );
+    }
+
+    if config.exit_when_done {
+        if share.map().read().unwrap().is_limited() {
+            let share = share.clone();
+            let shutdown = shutdown.clone();
+
+            spawn(async move {
+                share.wait_done().await;
+                log::info!("all links are consumed, stopping server...");
+                shutdown.cancel();
+            });
+        } else {
+            log::warn!(
+                "exit_when_done has no effect because some links have no download limit or ttl"
+            );
         }
-    };
+    }
 
-    if let Ok(external_ip) = gateway.get_external_ip().await {
-        if ip != external_ip {
-            log::warn!("NAT detected external_ip: {external_ip}");
-            log::warn!("use {external_ip} instead when sharing over WAN");
+    if let Some(port) = config.metrics_port {
+        let addr = SocketAddr::new(config.bind_address, port.get());
+        match app::bind(addr) {
+            Ok(listener) => {
+                log::info!("metrics available url: http://{addr}/metrics");
+
+                let share = share.clone();
+                spawn(async move {
+                    let Err(err) = share.serve_metrics(listener).await;
+                    log::error!("metrics server stopped err: {err}");
+                });
+            }
+            Err(err) => log::error!("cannot serve metrics port: {port} err: {err}"),
         }
     }
 
-    let IpAddr::V4(ip) = ip else {
-        return;
+    match (host, &config.unix_socket) {
+        (Some(host), _) => log::info!("server starting on {scheme}://{host}/"),
+        (None, Some(path)) => {
+            log::info!("server starting on unix socket path: {}", path.display())
+        }
+        (None, None) => {}
+    }
+
+    select! {
+        _ = shutdown.cancelled() => {
+            if let Some(service) = service {
+                let _ = service.await;
+            }
+
+            if let Some(mdns) = mdns {
+                let _ = mdns.await;
+            }
+        }
+        _ = share.serve(listener) => {}
     };
 
-    let port = port.get();
+    if let Some(ref path) = config.unix_socket {
+        if let Err(err) = std::fs::remove_file(path) {
+            log::warn!(
+                "cannot remove unix socket path: {} err: {err}",
+                path.display()
+            );
+        }
+    }
 
-    let task = async {
-        const TIMEOUT: Duration = Duration::from_secs(120);
+    Ok(())
+}
 
-        'task_loop: loop {
-            let mut attempts = 0;
-            while let Err(err) = gateway
-                .add_port(
-                    PortMappingProtocol::TCP,
-                    port,
-                    SocketAddrV4::new(ip, port),
-                    TIMEOUT.as_secs() as u32,
-                    "DirectShare port mapping",
-                )
-                .await
-            {
-                if attempts >= 5 {
-                    log::error!("uPnP port mapping failed, please do port forwarding manually or cannot be shared over WAN");
-                    break 'task_loop;
-                }
+/// Bind server listener. Following ports are tried if `auto_port` is enabled.
+/// Port in config is updated to the bound port.
+fn bind_listener(config: &mut DirectShareConfig) -> io::Result<TcpListener> {
+    const MAX_ATTEMPTS: u16 = 100;
+
+    let mut port = config.port;
+    let mut attempts = 0;
+    loop {
+        match app::bind(SocketAddr::new(config.bind_address, port.get())) {
+            Ok(listener) => {
+                config.port = port;
+                return Ok(listener);
+            }
+
+            Err(err) if err.kind() == ErrorKind::AddrInUse => {
+                let next = port.checked_add(1).filter(|_| attempts < MAX_ATTEMPTS);
 
-                let next = Duration::from_secs(5 + attempts * 5);
-                log::warn!(
-                    "uPnP port mapping failed, retrying after {} secs err: {err}",
-                    next.as_secs()
-                );
+                match next {
+                    Some(next) if config.auto_port => {
+                        log::warn!("port: {port} is already in use, trying port: {next}");
+                        port = next;
+                        attempts += 1;
+                    }
 
-                sleep(next).await;
-                attempts += 1;
+                    _ => {
+                        if !config.auto_port {
+                            log::error!("port: {port} is already in use. change `port` in config, use --port or enable `auto_port`");
+                        }
+
+                        return Err(err);
+                    }
+                }
             }
 
-            sleep(TIMEOUT).await;
+            Err(err) => return Err(err),
         }
-    };
+    }
+}
 
-    let cleanup = async {
-        if signal::ctrl_c().await.is_err() {
-            log::warn!("SIGINT signal hook failed.");
-            return;
-        };
+#[derive(Debug, Serialize)]
+/// Registered link printed with `--print-json`
+struct LinkSummary {
+    /// Shared path, `None` for bundle and stdin content
+    path: Option<PathBuf>,
+    key: String,
+    url: String,
+    kind: String,
 
-        let _ = gateway.remove_port(PortMappingProtocol::TCP, port).await;
-    };
+    /// Address used in url, `None` on unix socket
+    ip: Option<IpAddr>,
+
+    /// Address that server binds to, `None` on unix socket
+    bind_address: Option<IpAddr>,
+    port: Option<u16>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unix_socket: Option<PathBuf>,
+}
+
+/// Bind unix domain socket listener. Socket left by previous run is replaced
+#[cfg(unix)]
+fn bind_unix(path: &Path) -> io::Result<Listener> {
+    use std::os::unix::fs::FileTypeExt;
+
+    if std::fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket()) {
+        std::fs::remove_file(path)?;
+    }
+
+    Ok(UnixListener::bind(path)?.into())
+}
+
+#[cfg(not(unix))]
+fn bind_unix(_: &Path) -> io::Result<Listener> {
+    Err(io::Error::new(
+        ErrorKind::Unsupported,
+        "unix socket is not supported on this platform",
+    ))
+}
+
+/// Wait for SIGINT or SIGTERM
+#[cfg(unix)]
+async fn shutdown_signal() -> io::Result<()> {
+    let mut terminate = signal::unix::signal(signal::unix::SignalKind::terminate())?;
 
     select! {
-        _ = task => {}
-        _ = cleanup => {}
+        res = signal::ctrl_c() => res,
+        _ = terminate.recv() => Ok(()),
     }
 }
 
-async fn response(
-    addr: SocketAddr,
-    map: &PathMap,
-    req: Request<hyper::body::Incoming>,
-) -> Response<BoxBody<Bytes, io::Error>> {
-    let method = req.method();
-    let path = {
-        let mut chars = req.uri().path().chars();
-        chars.next();
-
-        chars.as_str()
-    };
+/// Wait for Ctrl-C
+#[cfg(not(unix))]
+async fn shutdown_signal() -> io::Result<()> {
+    signal::ctrl_c().await
+}
 
-    log::info!("method: {method} path: {path} addr: {addr}");
+/// Toggle paused state on every SIGUSR1
+#[cfg(unix)]
+async fn pause_signal(pause: &Pause) -> io::Result<()> {
+    let mut signal = signal::unix::signal(signal::unix::SignalKind::user_defined1())?;
 
-    if Method::GET != method {
-        return not_found_page();
+    while signal.recv().await.is_some() {
+        pause.toggle();
     }
 
-    let Some(file_path) = map.get(path) else {
-        return not_found_page();
-    };
+    Ok(())
+}
 
-    let meta = match fs::metadata(file_path).await {
-        Ok(meta) => meta,
-        Err(err) => {
-            log::error!("cannot stat {} err: {err}", file_path.display());
+/// Print qr code of the url to terminal
+fn print_qr(url: &str) {
+    match QrCode::new(url) {
+        // Draw light modules so the code scans on dark terminal
+        Ok(code) => println!(
+            "{}",
+            code.render::<Dense1x2>()
+                .dark_color(Dense1x2::Light)
+                .light_color(Dense1x2::Dark)
+                .build()
+        ),
+
+        Err(err) => log::warn!("cannot create qr code url: {url} err: {err}"),
+    }
+}
+
+/// Copy text to system clipboard. Returns the clipboard owning the content
+fn copy_to_clipboard(text: &str) -> Option<Clipboard> {
+    let res = Clipboard::new().and_then(|mut clipboard| {
+        clipboard.set_text(text)?;
+        Ok(clipboard)
+    });
 
-            return not_found_page();
+    match res {
+        Ok(clipboard) => {
+            log::info!("copied urls to clipboard");
+            Some(clipboard)
         }
-    };
 
-    let file_name = file_path
-        .file_name()
-        .map(|os_str| os_str.to_string_lossy().to_string())
-        .unwrap_or(constants::FALLBACK_FILENAME.into());
+        Err(err) => {
+            log::warn!("cannot copy urls to clipboard err: {err}");
+            None
+        }
+    }
+}
+
+/// Host name of this machine, `None` if unavailable
+fn local_hostname() -> Option<String> {
+    let hostname = gethostname::gethostname().into_string().ok()?;
+    let hostname = hostname.trim_end_matches(".local");
 
-    if meta.is_file() {
-        log::info!("serving file: {} addr: {addr}", file_path.display());
-        serve_file(file_path.as_path(), &file_name, meta, req).await
+    if hostname.is_empty() || hostname == "localhost" {
+        None
     } else {
-        log::info!("serving directory: {} addr: {addr}", file_path.display());
-        serve_directory(file_path.as_path(), &file_name, req).await
+        Some(hostname.to_string())
     }
 }
 
-async fn serve_file(
-    path: &Path,
-    file_name: &str,
-    meta: Metadata,
-    _req: Request<hyper::body::Incoming>,
-) -> Response<BoxBody<Bytes, io::Error>> {
-    let file = match File::open(path).await {
-        Ok(file) => file,
+/// Address used in shared urls
+fn advertised_ip(bind_address: IpAddr) -> IpAddr {
+    if !bind_address.is_unspecified() {
+        return bind_address;
+    }
+
+    let ip = match bind_address {
+        IpAddr::V4(_) => local_ip(),
+        // Dual-stack socket accepts IPv4 connections too
+        IpAddr::V6(_) => local_ip().or_else(|_| local_ipv6()),
+    };
+
+    match ip {
+        Ok(ip) => ip,
         Err(err) => {
-            log::error!("cannot open file path: {} err: {err}", path.display());
-            return not_found_page();
+            log::warn!("cannot find local ip address, using loopback address err: {err}");
+
+            match bind_address {
+                IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+                IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            }
+        }
+    }
+}
+
+async fn upnp_service(ip: IpAddr, share: DirectShare, shutdown: CancellationToken) {
+    let config = share.config();
+
+    let gateway = match search_gateway(SearchOptions::default()).await {
+        Ok(gateway) => gateway,
+        Err(err) => {
+            log::warn!("uPnP discovery failed err: {err}");
+            return;
         }
     };
 
-    let mut res = Response::new(
-        StreamBody::new(ReaderStream::with_capacity(file, FILE_BUF_SIZE).map_ok(Frame::data))
-            .boxed(),
-    );
-
-    let headers = res.headers_mut();
-    headers.insert(
-        header::CONTENT_LENGTH,
-        meta.len().to_string().parse().unwrap(),
-    );
-    headers.insert(
-        header::CONTENT_DISPOSITION,
-        format!("attachment; filename={}", file_name)
-            .parse()
-            .unwrap(),
-    );
-
-    res
-}
+    // Address reachable from WAN, `None` if lan urls can be used as is
+    // Public address is already known if `public_host` is set
+    let mut external_ip = match gateway.get_external_ip().await {
+        Ok(external_ip) if ip != external_ip && config.public_host.is_none() => {
+            log::warn!("NAT detected external_ip: {external_ip}");
+            log::warn!("use {external_ip} instead when sharing over WAN");
+            Some(external_ip)
+        }
 
-async fn serve_directory(
-    path: &Path,
-    dir_name: &str,
-    _req: Request<hyper::body::Incoming>,
-) -> Response<BoxBody<Bytes, io::Error>> {
-    let archive_name = format!("{dir_name}.tar");
+        _ => None,
+    };
 
-    let (tx, rx) = duplex(TAR_BUF_SIZE);
+    let IpAddr::V4(ip) = ip else {
+        log::info!("uPnP port mapping skipped for IPv6 address");
+        return;
+    };
 
-    tokio::spawn({
-        let path = path.to_path_buf();
+    let port = config.port.get();
+    let lease = Duration::from_secs(config.upnp_lease_secs as u64);
 
-        async move {
-            let mut ar = tokio_tar::Builder::new_non_terminated(tx);
-            ar.append_dir_all(".", path).await?;
-            ar.finish().await?;
+    // Protocols mapped at least once, removed on cleanup
+    let mapped = Mutex::new(Vec::<PortMappingProtocol>::new());
 
-            Ok::<_, io::Error>(())
-        }
-    });
+    let task = async {
+        'task_loop: loop {
+            for &protocol in config.upnp_protocol.protocols() {
+                let mut attempts = 0;
+                while let Err(err) = gateway
+                    .add_port(
+                        protocol,
+                        port,
+                        SocketAddrV4::new(ip, port),
+                        config.upnp_lease_secs,
+                        "DirectShare port mapping",
+                    )
+                    .await
+                {
+                    if attempts >= config.upnp_max_retries {
+                        log::error!("uPnP port mapping failed protocol: {protocol}, please do port forwarding manually or cannot be shared over WAN");
+                        break 'task_loop;
+                    }
+
+                    let next =
+                        Duration::from_secs(config.upnp_retry_base_secs * (attempts as u64 + 1));
+                    log::warn!(
+                        "uPnP port mapping failed protocol: {protocol}, retrying after {} secs err: {err}",
+                        next.as_secs()
+                    );
+
+                    sleep(next).await;
+                    attempts += 1;
+                }
 
-    let mut res = Response::new(StreamBody::new(ReaderStream::new(rx).map_ok(Frame::data)).boxed());
+                let mut mapped = mapped.lock().unwrap();
+                if !mapped.contains(&protocol) {
+                    log::info!("uPnP port mapped protocol: {protocol} port: {port}");
+                    mapped.push(protocol);
+                }
+            }
 
-    let headers = res.headers_mut();
-    headers.insert(
-        header::CONTENT_DISPOSITION,
-        format!("attachment; filename={}", archive_name)
-            .parse()
-            .unwrap(),
-    );
+            if let Some(external_ip) = external_ip.take() {
+                let host = SocketAddr::new(external_ip.into(), port);
+                for (key, target) in share.map().read().unwrap().links() {
+                    log::info!(
+                        "{target} wan url: {}",
+                        map::link_url(share.scheme(), host, &config.prefixed(key))
+                    );
+                }
+            }
 
-    res
-}
+            // Lease of 0 never expires
+            if lease.is_zero() {
+                return pending().await;
+            }
+
+            sleep(lease).await;
+        }
+    };
+
+    let cleanup = async {
+        shutdown.cancelled().await;
+
+        let mapped = mapped.lock().unwrap().clone();
+        for protocol in mapped {
+            match gateway.remove_port(protocol, port).await {
+                Ok(_) => log::info!("uPnP port unmapped protocol: {protocol} port: {port}"),
+                Err(err) => {
+                    log::warn!("uPnP port unmapping failed protocol: {protocol} err: {err}")
+                }
+            }
+        }
+    };
 
-fn not_found_page() -> Response<BoxBody<Bytes, io::Error>> {
-    Response::builder()
-        .status(StatusCode::NOT_FOUND)
-        .body(Empty::<Bytes>::new().map_err(|_| unreachable!()).boxed())
-        .unwrap()
+    select! {
+        _ = task => {}
+        _ = cleanup => {}
+    }
 }
 
-async fn load_config() -> DirectShareConfig {
+async fn load_config(path: &Path) -> DirectShareConfig {
     #[derive(Debug, Error)]
     pub enum Error {
         #[error(transparent)]
@@ -342,16 +788,17 @@ async fn load_config() -> DirectShareConfig {
         Unreadable(#[from] io::Error),
     }
 
-    async fn load() -> Result<DirectShareConfig, Error> {
-        let data = fs::read_to_string(constants::CONFIG_FILE)
-            .await
-            .map_err(Error::Unreadable)?;
+    async fn load(path: &Path) -> Result<DirectShareConfig, Error> {
+        let data = fs::read_to_string(path).await.map_err(Error::Unreadable)?;
 
         toml::from_str::<DirectShareConfig>(&data).map_err(Error::Invalid)
     }
 
-    match load().await {
-        Ok(config) => config,
+    match load(path).await {
+        Ok(config) => {
+            log::info!("config loaded path: {}", path.display());
+            config
+        }
 
         Err(Error::Unreadable(err)) => {
             log::warn!("config is unreadable. using default config. err: {err}");
@@ -360,15 +807,10 @@ async fn load_config() -> DirectShareConfig {
 
             if err.kind() == ErrorKind::NotFound {
                 log::info!("creating default config...");
-                if let Err(write_err) = fs::write(
-                    constants::CONFIG_FILE,
-                    toml::to_string_pretty(&config).unwrap(),
-                )
-                .await
-                {
+                if let Err(write_err) = write_default(path, &config).await {
                     log::warn!("cannot write default config err: {write_err}");
                 } else {
-                    log::info!("default config written");
+                    log::info!("default config written path: {}", path.display());
                 }
             }
 
@@ -384,3 +826,11 @@ async fn load_config() -> DirectShareConfig {
         }
     }
 }
+
+async fn write_default(path: &Path, config: &DirectShareConfig) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    fs::write(path, toml::to_string_pretty(config).unwrap()).await
+}
@@ -4,67 +4,1454 @@
  * Copyright (c) storycraft. Licensed under the MIT Licence.
  */
 
-use std::{collections::HashMap, num::NonZeroU8, path::PathBuf};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fmt,
+    fs::{self, File},
+    io::{self, ErrorKind, Write},
+    net::{IpAddr, SocketAddr},
+    num::NonZeroU8,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Arc, LazyLock, Mutex,
+    },
+    time::{Duration, SystemTime},
+};
 
+use hyper::body::Bytes;
 use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
 
-#[derive(Debug, Clone)]
+use crate::{
+    archive::{WalkOptions, Walker},
+    auth::PasswordHash,
+    config::{KeyAlphabet, KeyStyle},
+    constants::{ADMIN_PATH, UPLOAD_PATH},
+};
+
+/// Embedded wordlist for word style keys
+static WORDS: LazyLock<Vec<&'static str>> =
+    LazyLock::new(|| include_str!("words.txt").lines().collect());
+
+#[derive(Debug)]
 pub struct PathMap {
     key_length: NonZeroU8,
-    map: HashMap<String, PathBuf>,
+    style: KeyStyle,
+    alphabet: KeyAlphabet,
+
+    /// Keys are stored and looked up in lowercase
+    case_insensitive: bool,
+
+    /// Generator used instead of key style and alphabet if set
+    generator: Option<Box<dyn KeyGenerator>>,
+
+    map: HashMap<String, Entry>,
+
+    /// File where entries are persisted
+    file: Option<Arc<LinksFile>>,
+}
+
+#[derive(Debug)]
+/// File where entries are persisted.
+/// Saves may finish out of order, so snapshots older than the written one are dropped
+struct LinksFile {
+    path: PathBuf,
+
+    /// Sequence number of latest snapshot
+    latest: AtomicU64,
+
+    /// Sequence number of snapshot in the file. Held while writing
+    written: Mutex<u64>,
+}
+
+impl LinksFile {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            latest: AtomicU64::new(0),
+            written: Mutex::new(0),
+        }
+    }
+
+    fn next_seq(&self) -> u64 {
+        self.latest.fetch_add(1, Ordering::AcqRel) + 1
+    }
+
+    /// Replace file with snapshot `seq`. Written to temporary file first,
+    /// so crash while writing leaves previous file intact
+    fn write(&self, seq: u64, data: &str) -> io::Result<()> {
+        let mut written = self.written.lock().unwrap();
+        if *written > seq {
+            return Ok(());
+        }
+
+        let mut temp = self.path.clone().into_os_string();
+        temp.push(".tmp");
+
+        let mut file = File::create(&temp)?;
+        file.write_all(data.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&temp, &self.path)?;
+
+        *written = seq;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+/// Error registering custom key
+pub enum KeyError {
+    #[error("key must be 1 to 64 characters of letters, digits, `-` or `_`")]
+    Invalid,
+    #[error("key is reserved")]
+    Reserved,
+    #[error("key is already taken")]
+    Taken,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+#[derive(Debug, Clone, Default)]
+/// Options applied to registered link
+pub struct LinkOptions {
+    /// Duration until the link expires
+    pub ttl: Option<Duration>,
+
+    /// Maximum download count of the link
+    pub download_limit: Option<u32>,
+
+    /// Password required to download the link
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+/// Shared target of a link
+pub enum Target {
+    /// Single file or directory
+    Path(PathBuf),
+
+    /// Multiple paths downloaded as one archive
+    Bundle(Vec<PathBuf>),
+
+    /// In memory content downloaded as file named `name`. Never persisted
+    #[serde(skip)]
+    Bytes { name: String, data: Bytes },
+}
+
+impl Target {
+    /// Check if every path of the target exists
+    pub fn exists(&self) -> bool {
+        match self {
+            Target::Path(path) => path.exists(),
+            Target::Bundle(paths) => paths.iter().all(|path| path.exists()),
+            Target::Bytes { .. } => true,
+        }
+    }
+
+    /// Total size of files in the target including hidden ones.
+    /// Directories are walked without following symlinks
+    pub async fn size(&self) -> io::Result<u64> {
+        match self {
+            Target::Path(path) => path_size(path).await,
+
+            Target::Bundle(paths) => {
+                let mut size = 0;
+                for path in paths {
+                    size += path_size(path).await?;
+                }
+
+                Ok(size)
+            }
+
+            Target::Bytes { data, .. } => Ok(data.len() as u64),
+        }
+    }
+}
+
+/// Size of file or every file under directory
+async fn path_size(path: &Path) -> io::Result<u64> {
+    let meta = tokio::fs::metadata(path).await?;
+    if !meta.is_dir() {
+        return Ok(meta.len());
+    }
+
+    let mut size = 0;
+    let options = WalkOptions {
+        include_hidden: true,
+        ..Default::default()
+    };
+
+    let mut walker = Walker::new(path, &options).await?;
+    while let Some(entry) = walker.next().await? {
+        if !entry.meta.is_dir() {
+            size += entry.meta.len();
+        }
+    }
+
+    Ok(size)
+}
+
+/// Total size of `targets`. Targets which cannot be read are skipped
+pub async fn total_size(targets: impl IntoIterator<Item = Target>) -> u64 {
+    let mut size = 0;
+    for target in targets {
+        match target.size().await {
+            Ok(target_size) => size += target_size,
+            Err(err) => log::warn!("cannot read size path: {target} err: {err}"),
+        }
+    }
+
+    size
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::Path(path) => write!(f, "{}", path.display()),
+            Target::Bundle(paths) => write!(f, "bundle of {} paths", paths.len()),
+            Target::Bytes { name, data } => write!(f, "{name} ({} bytes in memory)", data.len()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+/// Kind of registered path
+pub enum PathKind {
+    File,
+    Directory,
+}
+
+impl fmt::Display for PathKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathKind::File => f.write_str("file"),
+            PathKind::Directory => f.write_str("directory"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// Information of registered path
+pub struct Registration {
+    pub key: String,
+
+    /// Absolute path of the registered path. Symlink is resolved to its target
+    pub path: PathBuf,
+
+    pub kind: PathKind,
+
+    /// Size of file in bytes, `None` for directory
+    pub size: Option<u64>,
+}
+
+impl Registration {
+    /// Create information of path registered under `key`.
+    /// Symlink is resolved to its target, so it is served like the target itself.
+    pub fn new(key: String, path: &Path) -> io::Result<Self> {
+        let absolute = std::path::absolute(path)?;
+        let path = resolve_path(&absolute)?;
+        if path != absolute {
+            log::info!(
+                "resolved symlink path: {} target: {}",
+                absolute.display(),
+                path.display()
+            );
+        }
+
+        let meta = fs::metadata(&path)?;
+
+        let (kind, size) = if meta.is_dir() {
+            (PathKind::Directory, None)
+        } else {
+            (PathKind::File, Some(meta.len()))
+        };
+
+        Ok(Self {
+            key,
+            path,
+            kind,
+            size,
+        })
+    }
+
+    /// Shareable url of the link
+    pub fn to_url(&self, scheme: &str, ip: IpAddr, port: u16) -> String {
+        link_url(scheme, SocketAddr::new(ip, port), &self.key)
+    }
+}
+
+/// Absolute path of `path` as registered. Symlink is resolved to its target
+pub fn resolve_path(path: &Path) -> io::Result<PathBuf> {
+    let path = std::path::absolute(path)?;
+    if fs::symlink_metadata(&path)?.is_symlink() {
+        fs::canonicalize(&path)
+    } else {
+        Ok(path)
+    }
+}
+
+impl fmt::Display for Registration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.kind, self.path.display())
+    }
+}
+
+/// Shareable url of the link `key` served on `host`
+pub fn link_url(scheme: &str, host: SocketAddr, key: &str) -> String {
+    format!("{scheme}://{host}/{key}")
+}
+
+/// Shareable url of the link `key` served on `port` of public `host` name
+pub fn public_url(scheme: &str, host: &str, port: u16, key: &str) -> String {
+    format!("{scheme}://{host}:{port}/{key}")
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    #[serde(rename = "path")]
+    target: Target,
+
+    /// Time after the entry is no longer available
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<SystemTime>,
+
+    /// Remaining download count
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    remaining: Option<AtomicU32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    password: Option<PasswordHash>,
+}
+
+impl Entry {
+    fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.remaining
+            .as_ref()
+            .is_some_and(|remaining| remaining.load(Ordering::Acquire) == 0)
+    }
+
+    fn is_available(&self, now: SystemTime) -> bool {
+        !self.is_expired(now) && !self.is_exhausted()
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SavedLinks {
+    links: HashMap<String, Entry>,
+}
+
+#[derive(Debug, Serialize)]
+struct SavedLinksRef<'a> {
+    links: HashMap<&'a str, &'a Entry>,
 }
 
 impl PathMap {
     pub fn new(key_length: NonZeroU8) -> Self {
         Self {
             key_length,
+            style: KeyStyle::Random,
+            alphabet: KeyAlphabet::Full,
+            case_insensitive: false,
+            generator: None,
             map: HashMap::new(),
+            file: None,
+        }
+    }
+
+    /// Create path map persisted in file.
+    /// Previously saved entries are loaded and unavailable or missing entries are pruned.
+    /// File is not written until entries change, pruned entries are dropped from it then.
+    pub fn persistent(key_length: NonZeroU8, file: PathBuf) -> io::Result<Self> {
+        let saved = match fs::read_to_string(&file) {
+            Ok(data) => toml::from_str::<SavedLinks>(&data)
+                .map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?,
+
+            Err(err) if err.kind() == ErrorKind::NotFound => SavedLinks::default(),
+
+            Err(err) => return Err(err),
+        };
+
+        let now = SystemTime::now();
+
+        let mut map = HashMap::new();
+        for (key, entry) in saved.links {
+            if !entry.is_available(now) {
+                log::info!(
+                    "pruning link key: {key} path: {} (unavailable)",
+                    entry.target
+                );
+                continue;
+            }
+
+            if !entry.target.exists() {
+                log::info!("pruning link key: {key} path: {} (missing)", entry.target);
+                continue;
+            }
+
+            map.insert(key, entry);
         }
+
+        Ok(Self {
+            key_length,
+            style: KeyStyle::Random,
+            alphabet: KeyAlphabet::Full,
+            case_insensitive: false,
+            generator: None,
+            map,
+            file: Some(Arc::new(LinksFile::new(file))),
+        })
+    }
+
+    /// Keep entries in memory only. Changes are not saved to file anymore
+    pub fn without_file(mut self) -> Self {
+        self.file = None;
+        self
     }
 
-    /// Get file path from shorten uri
+    /// Use `style` for generating new keys
+    pub fn with_style(mut self, style: KeyStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Use `alphabet` for generating new keys
+    pub fn with_alphabet(mut self, alphabet: KeyAlphabet) -> Self {
+        self.alphabet = alphabet;
+        self
+    }
+
+    /// Generate keys of new links using `generator`, replacing key style and alphabet
+    pub fn with_generator(mut self, generator: impl KeyGenerator + 'static) -> Self {
+        self.generator = Some(Box::new(generator));
+        self
+    }
+
+    /// Store and look up keys in lowercase if `case_insensitive` is set.
+    /// Generated keys only use lowercase characters so effective alphabet is smaller.
+    pub fn with_case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+
+        if case_insensitive {
+            let mut map = HashMap::with_capacity(self.map.len());
+            for (key, entry) in self.map.drain() {
+                let key = key.to_ascii_lowercase();
+                if map.contains_key(&key) {
+                    log::warn!("dropping link with case insensitive duplicate key: {key}");
+                    continue;
+                }
+
+                map.insert(key, entry);
+            }
+            self.map = map;
+        }
+
+        self
+    }
+
+    /// Key in stored case
+    fn normalize<'a>(&self, key: &'a str) -> Cow<'a, str> {
+        if self.case_insensitive {
+            Cow::Owned(key.to_ascii_lowercase())
+        } else {
+            Cow::Borrowed(key)
+        }
+    }
+
+    /// Check if keys of new path links are derived from file content.
+    /// Content should be hashed before locking the map, see [`PathMap::register_with_digest`]
+    pub fn hashes_content(&self) -> bool {
+        self.style == KeyStyle::ContentHash && self.generator.is_none()
+    }
+
+    /// Characters used for generating keys
+    fn key_chars(&self) -> Vec<char> {
+        self.alphabet
+            .chars()
+            .iter()
+            .copied()
+            .filter(|ch| !self.case_insensitive || !ch.is_ascii_uppercase())
+            .collect()
+    }
+
+    /// Find key of registered path
+    pub fn find_key(&self, path: &Path) -> Option<&str> {
+        let now = SystemTime::now();
+
+        self.map
+            .iter()
+            .find(|(_, entry)| {
+                matches!(entry.target, Target::Path(ref target) if target == path)
+                    && entry.is_available(now)
+            })
+            .map(|(key, _)| key.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Iterate keys and targets of every entry including unavailable ones
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Target)> {
+        self.map
+            .iter()
+            .map(|(key, entry)| (key.as_str(), &entry.target))
+    }
+
+    /// Iterate keys and targets of available entries
+    pub fn links(&self) -> impl Iterator<Item = (&str, &Target)> {
+        let now = SystemTime::now();
+
+        self.map
+            .iter()
+            .filter(move |(_, entry)| entry.is_available(now))
+            .map(|(key, entry)| (key.as_str(), &entry.target))
+    }
+
+    /// Check if every entry is limited by download count or ttl
+    pub fn is_limited(&self) -> bool {
+        self.map
+            .values()
+            .all(|entry| entry.expires_at.is_some() || entry.remaining.is_some())
+    }
+
+    /// Check if no entry is available anymore
+    pub fn is_done(&self) -> bool {
+        let now = SystemTime::now();
+
+        self.map.values().all(|entry| !entry.is_available(now))
+    }
+
+    /// Get file path from shorten uri. Returns `None` if the target is not a single path
     pub fn get(&self, path: &str) -> Option<&PathBuf> {
-        self.map.get(path)
+        match self.get_target(path)? {
+            Target::Path(path) => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Get shared target from shorten uri
+    pub fn get_target(&self, path: &str) -> Option<&Target> {
+        self.map
+            .get(self.normalize(path).as_ref())
+            .filter(|entry| entry.is_available(SystemTime::now()))
+            .map(|entry| &entry.target)
+    }
+
+    /// Check if the entry requires password
+    pub fn is_password_protected(&self, path: &str) -> bool {
+        self.map
+            .get(self.normalize(path).as_ref())
+            .is_some_and(|entry| entry.password.is_some())
+    }
+
+    /// Check if the entry has limited download count
+    pub fn is_download_limited(&self, path: &str) -> bool {
+        self.map
+            .get(self.normalize(path).as_ref())
+            .is_some_and(|entry| entry.remaining.is_some())
+    }
+
+    /// Verify password of the entry. Always succeeds if the entry has no password.
+    pub fn verify_password(&self, path: &str, password: &str) -> bool {
+        match self.map.get(self.normalize(path).as_ref()) {
+            Some(Entry {
+                password: Some(hash),
+                ..
+            }) => hash.verify(password),
+
+            Some(_) => true,
+
+            None => false,
+        }
+    }
+
+    /// Consume one download of the entry.
+    /// Returns `false` if the entry does not exist or has no download left.
+    pub fn consume(&self, path: &str) -> bool {
+        let Some(entry) = self.map.get(self.normalize(path).as_ref()) else {
+            return false;
+        };
+
+        let Some(ref remaining) = entry.remaining else {
+            return true;
+        };
+
+        let consumed = remaining
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                count.checked_sub(1)
+            })
+            .is_ok();
+
+        if consumed {
+            if let Err(err) = self.save() {
+                log::warn!("cannot save links err: {err}");
+            }
+        }
+
+        consumed
     }
 
     /// Register new path and return path
     pub fn register(&mut self, path: PathBuf) -> String {
-        let key = gen_key(self.key_length.get() as usize);
+        self.register_with_options(path, &LinkOptions::default())
+    }
+
+    /// Register new path expiring after `ttl` and return path
+    pub fn register_with_ttl(&mut self, path: PathBuf, ttl: Duration) -> String {
+        self.register_with_options(
+            path,
+            &LinkOptions {
+                ttl: Some(ttl),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Register new path which can be downloaded `count` times and return path
+    pub fn register_with_limit(&mut self, path: PathBuf, count: u32) -> String {
+        self.register_with_options(
+            path,
+            &LinkOptions {
+                download_limit: Some(count),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Register new path protected with password and return path
+    pub fn register_with_password(&mut self, path: PathBuf, password: &str) -> String {
+        self.register_with_options(
+            path,
+            &LinkOptions {
+                password: Some(password.to_string()),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Register new path with options and return path.
+    /// File is read while registering if keys are derived from file content.
+    pub fn register_with_options(&mut self, path: PathBuf, options: &LinkOptions) -> String {
+        let digest = self.content_digest(&path);
+        self.register_with_digest(path, digest, options)
+    }
+
+    /// Register new path with sha256 `digest` of its content computed in advance and return path.
+    /// Key is derived from the path if `digest` is `None`.
+    pub fn register_with_digest(
+        &mut self,
+        path: PathBuf,
+        digest: Option<[u8; 32]>,
+        options: &LinkOptions,
+    ) -> String {
+        self.insert(Target::Path(path), digest, options)
+    }
+
+    /// Register existing path with options and return information of the link.
+    /// Path is stored as absolute path.
+    pub fn register_info(
+        &mut self,
+        path: &Path,
+        options: &LinkOptions,
+    ) -> io::Result<Registration> {
+        let digest = self.content_digest(path);
+        self.register_info_with_digest(path, digest, options)
+    }
+
+    /// Register existing path with sha256 `digest` of its content computed in advance
+    /// and return information of the link
+    pub fn register_info_with_digest(
+        &mut self,
+        path: &Path,
+        digest: Option<[u8; 32]>,
+        options: &LinkOptions,
+    ) -> io::Result<Registration> {
+        let mut registration = Registration::new(String::new(), path)?;
+        registration.key = self.register_with_digest(registration.path.clone(), digest, options);
+
+        Ok(registration)
+    }
+
+    /// Register new path under custom `key` and return the key
+    pub fn register_with_key(&mut self, key: String, path: PathBuf) -> Result<String, KeyError> {
+        self.register_with_key_options(key, path, &LinkOptions::default())
+    }
+
+    /// Register new path under custom `key` with options and return the key.
+    /// Registering same path under the key again replaces the link.
+    pub fn register_with_key_options(
+        &mut self,
+        key: String,
+        path: PathBuf,
+        options: &LinkOptions,
+    ) -> Result<String, KeyError> {
+        let key = self.normalize(&key).into_owned();
+        validate_key(&key)?;
+
+        let target = Target::Path(path);
+        if self
+            .map
+            .get(&key)
+            .is_some_and(|entry| entry.target != target)
+        {
+            return Err(KeyError::Taken);
+        }
+
+        Ok(self.insert_entry(key, target, options))
+    }
 
-        self.map.insert(key.clone(), path);
+    /// Register in memory content downloaded as file named `name` and return path
+    pub fn register_bytes(&mut self, name: String, data: Bytes, options: &LinkOptions) -> String {
+        self.insert(Target::Bytes { name, data }, None, options)
+    }
+
+    /// Register multiple paths downloaded as one archive and return path
+    pub fn register_bundle(&mut self, paths: Vec<PathBuf>, options: &LinkOptions) -> String {
+        self.insert(Target::Bundle(paths), None, options)
+    }
+
+    /// Sha256 of file content if keys are derived from it
+    fn content_digest(&self, path: &Path) -> Option<[u8; 32]> {
+        if !self.hashes_content() || !path.is_file() {
+            return None;
+        }
+
+        file_digest(path)
+            .inspect_err(|err| {
+                log::warn!(
+                    "cannot hash content, using path instead path: {} err: {err}",
+                    path.display()
+                )
+            })
+            .ok()
+    }
+
+    fn insert(
+        &mut self,
+        target: Target,
+        digest: Option<[u8; 32]>,
+        options: &LinkOptions,
+    ) -> String {
+        let key = match self.style {
+            KeyStyle::Hash | KeyStyle::ContentHash if self.generator.is_none() => {
+                self.hash_key(&target, digest)
+            }
+            _ => self.unique_key(),
+        };
+
+        self.insert_entry(key, target, options)
+    }
+
+    fn insert_entry(&mut self, key: String, target: Target, options: &LinkOptions) -> String {
+        self.map.insert(
+            key.clone(),
+            Entry {
+                target,
+                expires_at: options.ttl.map(|ttl| SystemTime::now() + ttl),
+                remaining: options.download_limit.map(AtomicU32::new),
+                password: options.password.as_deref().map(PasswordHash::new),
+            },
+        );
+
+        if let Err(err) = self.save() {
+            log::warn!("cannot save links err: {err}");
+        }
 
         key
     }
+
+    /// Remove registered path and return the target
+    pub fn unregister(&mut self, key: &str) -> Option<Target> {
+        let entry = self.map.remove(self.normalize(key).as_ref())?;
+
+        if let Err(err) = self.save() {
+            log::warn!("cannot save links err: {err}");
+        }
+
+        Some(entry.target)
+    }
+
+    /// Remove expired or exhausted entries and return count of removed entries
+    pub fn remove_stale(&mut self) -> usize {
+        let now = SystemTime::now();
+
+        let len = self.map.len();
+        self.map.retain(|key, entry| {
+            if entry.is_expired(now) {
+                log::info!("link expired key: {key} path: {}", entry.target);
+                false
+            } else if entry.is_exhausted() {
+                log::info!("link exhausted key: {key} path: {}", entry.target);
+                false
+            } else {
+                true
+            }
+        });
+
+        let removed = len - self.map.len();
+        if removed > 0 {
+            if let Err(err) = self.save() {
+                log::warn!("cannot save links err: {err}");
+            }
+        }
+
+        removed
+    }
+
+    /// Generate key not used by any entry.
+    /// Key length is widened if unused key cannot be found in few attempts.
+    fn unique_key(&self) -> String {
+        const MAX_ATTEMPTS: usize = 16;
+
+        let builtin;
+        let generator: &dyn KeyGenerator = match self.generator {
+            Some(ref generator) => generator.as_ref(),
+            None => {
+                builtin = self.builtin_generator();
+                builtin.as_ref()
+            }
+        };
+
+        let mut length = self.key_length.get() as usize;
+        loop {
+            for _ in 0..MAX_ATTEMPTS {
+                let key = generator.generate(length);
+
+                if !self.map.contains_key(&key) {
+                    return key;
+                }
+            }
+
+            length += 1;
+            log::warn!("key collided too many times, widening key length to {length}");
+        }
+    }
+
+    /// Generator of key style and alphabet
+    fn builtin_generator(&self) -> Box<dyn KeyGenerator> {
+        match self.style {
+            KeyStyle::Words => Box::new(WordKeys),
+            _ => Box::new(RandomKeys::new(self.key_chars())),
+        }
+    }
+
+    /// Generate key from hash of the target.
+    /// Returns key of the same target if registered already, salted key is used if collided with other target.
+    fn hash_key(&self, target: &Target, digest: Option<[u8; 32]>) -> String {
+        let input = hash_input(self.style, target, digest);
+        let chars = self.key_chars();
+
+        (0..)
+            .map(|salt| hash_key(&input, salt, self.key_length.get() as usize, &chars))
+            .find(|key| match self.map.get(key) {
+                Some(entry) => entry.target == *target,
+                None => true,
+            })
+            .unwrap()
+    }
+
+    /// Save entries to file. Writing is moved off async workers when called in runtime
+    fn save(&self) -> io::Result<()> {
+        let Some(ref file) = self.file else {
+            return Ok(());
+        };
+
+        let data = self.serialize()?;
+        let seq = file.next_seq();
+
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return file.write(seq, &data);
+        };
+
+        let file = file.clone();
+        handle.spawn_blocking(move || {
+            if let Err(err) = file.write(seq, &data) {
+                log::warn!("cannot save links err: {err}");
+            }
+        });
+
+        Ok(())
+    }
+
+    fn serialize(&self) -> io::Result<String> {
+        let links = self
+            .map
+            .iter()
+            .filter(|(_, entry)| !matches!(entry.target, Target::Bytes { .. }))
+            .map(|(key, entry)| (key.as_str(), entry))
+            .collect();
+
+        toml::to_string_pretty(&SavedLinksRef { links })
+            .map_err(|err| io::Error::new(ErrorKind::InvalidData, err))
+    }
+}
+
+/// Generator of keys for new links
+pub trait KeyGenerator: fmt::Debug + Send + Sync {
+    /// Generate key of `length`.
+    /// Length is widened by one if generated keys collide with existing ones too many times.
+    fn generate(&self, length: usize) -> String;
+}
+
+#[derive(Debug, Clone)]
+/// Random characters of the alphabet
+pub struct RandomKeys {
+    chars: Vec<char>,
+}
+
+impl RandomKeys {
+    pub fn new(chars: Vec<char>) -> Self {
+        Self { chars }
+    }
+}
+
+impl Default for RandomKeys {
+    fn default() -> Self {
+        Self::new(KeyAlphabet::Full.chars().to_vec())
+    }
+}
+
+impl KeyGenerator for RandomKeys {
+    fn generate(&self, length: usize) -> String {
+        gen_random_key(length, &self.chars)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+/// Random words joined by hyphens. Length is word count
+pub struct WordKeys;
+
+impl KeyGenerator for WordKeys {
+    fn generate(&self, length: usize) -> String {
+        gen_word_key(length)
+    }
+}
+
+/// Check if custom key is usable in url and not reserved for other routes
+fn validate_key(key: &str) -> Result<(), KeyError> {
+    const MAX_KEY_LEN: usize = 64;
+
+    if key.is_empty()
+        || key.len() > MAX_KEY_LEN
+        || !key
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+    {
+        return Err(KeyError::Invalid);
+    }
+
+    if key == ADMIN_PATH || key == UPLOAD_PATH {
+        return Err(KeyError::Reserved);
+    }
+
+    Ok(())
+}
+
+/// Sha256 of file content, read with blocking io
+pub fn file_digest(path: &Path) -> io::Result<[u8; 32]> {
+    let mut file = fs::File::open(path)?;
+
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+
+    Ok(hasher.finalize().into())
 }
 
-fn gen_key(size: usize) -> String {
-    const LIST: [char; 64] = [
-        '_', '-', '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'a', 'b', 'c', 'd', 'e', 'f',
-        'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x',
-        'y', 'z', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P',
-        'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z',
-    ];
+/// Bytes hashed for key of the target. Content `digest` of path is used for `ContentHash` style.
+fn hash_input(style: KeyStyle, target: &Target, digest: Option<[u8; 32]>) -> Vec<u8> {
+    fn path_bytes(path: &Path) -> Vec<u8> {
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+
+        path.to_string_lossy().into_owned().into_bytes()
+    }
 
+    match target {
+        Target::Path(path) => match digest {
+            Some(digest) if style == KeyStyle::ContentHash => digest.to_vec(),
+            _ => path_bytes(path),
+        },
+
+        Target::Bundle(paths) => paths
+            .iter()
+            .flat_map(|path| {
+                let mut bytes = path_bytes(path);
+                bytes.push(0);
+                bytes
+            })
+            .collect(),
+
+        Target::Bytes { name, data } => match style {
+            KeyStyle::ContentHash => data.to_vec(),
+            _ => name.as_bytes().to_vec(),
+        },
+    }
+}
+
+/// Key of `size` characters derived from sha256 hash of input and salt
+fn hash_key(input: &[u8], salt: u32, size: usize, alphabet: &[char]) -> String {
+    let mut key = String::with_capacity(size);
+
+    for block in 0u32.. {
+        let digest = Sha256::new()
+            .chain_update(input)
+            .chain_update(salt.to_le_bytes())
+            .chain_update(block.to_le_bytes())
+            .finalize();
+
+        for byte in digest {
+            if key.len() == size {
+                return key;
+            }
+
+            key.push(alphabet[byte as usize % alphabet.len()]);
+        }
+    }
+
+    key
+}
+
+fn gen_random_key(size: usize, alphabet: &[char]) -> String {
     let mut key = String::with_capacity(size);
 
     let mut rng = thread_rng();
     for _ in 0..size {
-        key.push(LIST[rng.gen_range(0..64)]);
+        key.push(alphabet[rng.gen_range(0..alphabet.len())]);
     }
 
     key
 }
 
+fn gen_word_key(count: usize) -> String {
+    let mut rng = thread_rng();
+
+    (0..count)
+        .map(|_| WORDS[rng.gen_range(0..WORDS.len())])
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::map::gen_key;
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        num::NonZeroU8,
+        path::{Path, PathBuf},
+        time::Duration,
+    };
+
+    use hyper::body::Bytes;
+
+    use crate::{
+        config::{KeyAlphabet, KeyStyle},
+        map::{
+            file_digest, resolve_path, total_size, KeyError, KeyGenerator, LinkOptions, LinksFile,
+            PathKind, PathMap, RandomKeys, Target, WordKeys,
+        },
+    };
 
     #[test]
     pub fn gen_key_test() {
-        let key = gen_key(21);
+        let key = RandomKeys::default().generate(21);
 
         println!("{}", key);
 
         assert_eq!(key.len(), 21)
     }
+
+    #[test]
+    pub fn gen_key_unambiguous_test() {
+        let key = RandomKeys::new(KeyAlphabet::Unambiguous.chars().to_vec()).generate(256);
+
+        assert_eq!(key.len(), 256);
+        assert!(!key.contains(['0', 'O', 'o', '1', 'l', 'I']));
+    }
+
+    #[test]
+    pub fn gen_word_key_test() {
+        let key = WordKeys.generate(3);
+
+        println!("{}", key);
+
+        let words: Vec<&str> = key.split('-').collect();
+        assert_eq!(words.len(), 3);
+        assert!(words
+            .iter()
+            .all(|word| !word.is_empty() && word.chars().all(|ch| ch.is_ascii_lowercase())));
+    }
+
+    #[test]
+    pub fn custom_generator_test() {
+        #[derive(Debug)]
+        struct Fixed;
+
+        impl KeyGenerator for Fixed {
+            fn generate(&self, length: usize) -> String {
+                "k".repeat(length)
+            }
+        }
+
+        let mut map = PathMap::new(NonZeroU8::new(2).unwrap())
+            .with_style(KeyStyle::Hash)
+            .with_generator(Fixed);
+
+        assert_eq!(map.register(PathBuf::from("a")), "kk");
+        // Collided key is widened
+        assert_eq!(map.register(PathBuf::from("b")), "kkk");
+    }
+
+    #[test]
+    pub fn register_collision_test() {
+        // Single character key only has 64 variations
+        let mut map = PathMap::new(NonZeroU8::new(1).unwrap());
+
+        let keys: Vec<(String, PathBuf)> = (0..128)
+            .map(|i| {
+                let path = PathBuf::from(format!("file{i}"));
+                (map.register(path.clone()), path)
+            })
+            .collect();
+
+        assert_eq!(map.len(), keys.len());
+        for (key, path) in keys {
+            assert_eq!(map.get(&key), Some(&path));
+        }
+    }
+
+    #[test]
+    pub fn register_word_collision_test() {
+        // Single word key has less variations than registered paths
+        let mut map = PathMap::new(NonZeroU8::new(1).unwrap()).with_style(KeyStyle::Words);
+
+        let keys: Vec<String> = (0..512)
+            .map(|i| map.register(PathBuf::from(format!("file{i}"))))
+            .collect();
+
+        assert_eq!(map.len(), keys.len());
+    }
+
+    #[test]
+    pub fn register_with_ttl_test() {
+        let mut map = PathMap::new(NonZeroU8::new(8).unwrap());
+
+        let expired = map.register_with_ttl("expired".into(), Duration::ZERO);
+        let alive = map.register_with_ttl("alive".into(), Duration::from_secs(3600));
+
+        assert_eq!(map.get(&expired), None);
+        assert_eq!(map.get(&alive), Some(&PathBuf::from("alive")));
+
+        assert_eq!(map.remove_stale(), 1);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    pub fn register_with_limit_test() {
+        let mut map = PathMap::new(NonZeroU8::new(8).unwrap());
+
+        let key = map.register_with_limit("file".into(), 2);
+
+        assert!(map.consume(&key));
+        assert!(map.consume(&key));
+        assert!(!map.consume(&key));
+        assert_eq!(map.get(&key), None);
+    }
+
+    #[test]
+    pub fn register_with_key_test() {
+        let mut map = PathMap::new(NonZeroU8::new(8).unwrap());
+
+        assert_eq!(
+            map.register_with_key("invoice".into(), "invoice.pdf".into())
+                .unwrap(),
+            "invoice"
+        );
+        assert_eq!(map.get("invoice"), Some(&PathBuf::from("invoice.pdf")));
+
+        // Same path can be registered again
+        assert!(map
+            .register_with_key("invoice".into(), "invoice.pdf".into())
+            .is_ok());
+
+        assert!(matches!(
+            map.register_with_key("invoice".into(), "other.pdf".into()),
+            Err(KeyError::Taken)
+        ));
+        assert!(matches!(
+            map.register_with_key("a/b".into(), "other.pdf".into()),
+            Err(KeyError::Invalid)
+        ));
+        assert!(matches!(
+            map.register_with_key("admin".into(), "other.pdf".into()),
+            Err(KeyError::Reserved)
+        ));
+    }
+
+    #[test]
+    pub fn case_insensitive_test() {
+        let mut map = PathMap::new(NonZeroU8::new(32).unwrap()).with_case_insensitive(true);
+
+        let key = map.register("file".into());
+        assert_eq!(key, key.to_ascii_lowercase());
+        assert_eq!(
+            map.get(&key.to_ascii_uppercase()),
+            Some(&PathBuf::from("file"))
+        );
+
+        map.register_with_key("Invoice".into(), "invoice.pdf".into())
+            .unwrap();
+        assert_eq!(map.get("INVOICE"), Some(&PathBuf::from("invoice.pdf")));
+
+        assert!(matches!(
+            map.register_with_key("ADMIN".into(), "other.pdf".into()),
+            Err(KeyError::Reserved)
+        ));
+    }
+
+    #[test]
+    pub fn register_hash_test() {
+        let mut map = PathMap::new(NonZeroU8::new(1).unwrap()).with_style(KeyStyle::Hash);
+
+        let key = map.register("file".into());
+        assert_eq!(map.register("file".into()), key);
+
+        // Keys of 1 character collide quickly
+        for i in 0..32 {
+            map.register(format!("file{i}").into());
+        }
+        assert_eq!(map.len(), 33);
+        assert_eq!(map.get(&key), Some(&PathBuf::from("file")));
+    }
+
+    #[test]
+    pub fn register_digest_test() {
+        let mut map = PathMap::new(NonZeroU8::new(8).unwrap()).with_style(KeyStyle::ContentHash);
+        assert!(map.hashes_content());
+
+        let path = PathBuf::from("Cargo.toml");
+        let key = map.register(path.clone());
+
+        // Digest computed before locking gives the same key
+        let digest = file_digest(&path).unwrap();
+        assert_eq!(
+            map.register_with_digest(path, Some(digest), &LinkOptions::default()),
+            key
+        );
+    }
+
+    #[test]
+    pub fn is_done_test() {
+        let mut map = PathMap::new(NonZeroU8::new(8).unwrap());
+
+        let key = map.register_with_limit("file".into(), 1);
+        assert!(map.is_limited());
+        assert!(!map.is_done());
+
+        assert!(map.consume(&key));
+        assert!(map.is_done());
+
+        map.register("open".into());
+        assert!(!map.is_limited());
+        assert!(!map.is_done());
+    }
+
+    #[test]
+    pub fn register_with_password_test() {
+        let mut map = PathMap::new(NonZeroU8::new(8).unwrap());
+
+        let key = map.register_with_password("file".into(), "secret");
+        let open_key = map.register("open".into());
+
+        assert!(map.is_password_protected(&key));
+        assert!(map.verify_password(&key, "secret"));
+        assert!(!map.verify_password(&key, "wrong"));
+
+        assert!(!map.is_password_protected(&open_key));
+        assert!(map.verify_password(&open_key, "anything"));
+    }
+
+    #[test]
+    pub fn unregister_test() {
+        let mut map = PathMap::new(NonZeroU8::new(8).unwrap());
+
+        let key = map.register("file".into());
+
+        assert_eq!(map.unregister(&key), Some(Target::Path("file".into())));
+        assert_eq!(map.get(&key), None);
+        assert_eq!(map.unregister(&key), None);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    pub fn len_test() {
+        let mut map = PathMap::new(NonZeroU8::new(8).unwrap());
+
+        let key = map.register("a".into());
+        map.register("b".into());
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.iter().count(), 2);
+
+        map.unregister(&key);
+        assert_eq!(map.len(), 1);
+        assert!(!map.is_empty());
+    }
+
+    #[tokio::test]
+    pub async fn total_size_test() {
+        let root = std::env::temp_dir().join("direct_share_total_size_test");
+        let _ = tokio::fs::remove_dir_all(&root).await;
+
+        tokio::fs::create_dir_all(root.join("dir")).await.unwrap();
+        tokio::fs::write(root.join("a.txt"), "hello").await.unwrap();
+        tokio::fs::write(root.join("dir").join("b.txt"), "world!")
+            .await
+            .unwrap();
+
+        let mut map = PathMap::new(NonZeroU8::new(8).unwrap());
+        map.register(root.join("a.txt"));
+        map.register(root.join("dir"));
+        map.register_bytes("c.txt".into(), Bytes::from("abc"), &LinkOptions::default());
+        map.register(root.join("missing"));
+
+        let targets: Vec<Target> = map.iter().map(|(_, target)| target.clone()).collect();
+        assert_eq!(total_size(targets).await, 14);
+
+        tokio::fs::remove_dir_all(root).await.unwrap();
+    }
+
+    #[test]
+    pub fn register_bundle_test() {
+        let mut map = PathMap::new(NonZeroU8::new(8).unwrap());
+
+        let paths = vec![PathBuf::from("a"), PathBuf::from("b")];
+        let key = map.register_bundle(paths.clone(), &LinkOptions::default());
+
+        assert_eq!(map.get(&key), None);
+        assert_eq!(map.get_target(&key), Some(&Target::Bundle(paths)));
+    }
+
+    #[test]
+    pub fn register_bytes_test() {
+        let file = std::env::temp_dir().join("direct_share_register_bytes_test.toml");
+        let _ = std::fs::remove_file(&file);
+
+        let mut map = PathMap::persistent(NonZeroU8::new(8).unwrap(), file.clone()).unwrap();
+        let bytes_key = map.register_bytes(
+            "report.txt".into(),
+            Bytes::from_static(b"report"),
+            &LinkOptions::default(),
+        );
+        let path_key = map.register(PathBuf::from("Cargo.toml"));
+
+        assert_eq!(map.get(&bytes_key), None);
+        assert!(matches!(
+            map.get_target(&bytes_key),
+            Some(Target::Bytes { name, .. }) if name == "report.txt"
+        ));
+
+        // In memory content is not saved
+        let map = PathMap::persistent(NonZeroU8::new(8).unwrap(), file.clone()).unwrap();
+        assert!(map.get_target(&bytes_key).is_none());
+        assert!(map.get_target(&path_key).is_some());
+
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    pub fn links_file_test() {
+        let path = std::env::temp_dir().join("direct_share_links_file_test.toml");
+        let file = LinksFile::new(path.clone());
+
+        let old = file.next_seq();
+        let new = file.next_seq();
+        file.write(new, "new").unwrap();
+
+        // Older snapshot finishing late does not overwrite newer one
+        file.write(old, "old").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        assert!(!path.with_extension("toml.tmp").exists());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    pub fn persistent_load_test() {
+        let file = std::env::temp_dir().join("direct_share_persistent_load_test.toml");
+        let data = "[links.missing]\npath = \"/direct_share_missing_file\"\n";
+        std::fs::write(&file, data).unwrap();
+
+        // Loading prunes in memory only
+        let map = PathMap::persistent(NonZeroU8::new(8).unwrap(), file.clone()).unwrap();
+        assert!(map.is_empty());
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), data);
+
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    pub fn persisted_symlink_test() {
+        let root = std::env::temp_dir().join("direct_share_persisted_symlink_test");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("target.txt"), "target").unwrap();
+        std::os::unix::fs::symlink(root.join("target.txt"), root.join("link.txt")).unwrap();
+
+        let file = root.join("links.toml");
+        let link = root.join("link.txt");
+
+        let mut map = PathMap::persistent(NonZeroU8::new(8).unwrap(), file.clone()).unwrap();
+        let key = map
+            .register_info(&link, &LinkOptions::default())
+            .unwrap()
+            .key;
+
+        // Sharing the symlink again after restart finds the saved link
+        let map = PathMap::persistent(NonZeroU8::new(8).unwrap(), file).unwrap();
+        assert_eq!(
+            map.find_key(&resolve_path(&link).unwrap()),
+            Some(key.as_str())
+        );
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    pub fn register_info_test() {
+        let mut map = PathMap::new(NonZeroU8::new(8).unwrap());
+
+        let file = map
+            .register_info(Path::new("Cargo.toml"), &LinkOptions::default())
+            .unwrap();
+        assert_eq!(file.kind, PathKind::File);
+        assert!(file.path.is_absolute());
+        assert!(file.size.is_some());
+        assert_eq!(map.get(&file.key), Some(&file.path));
+        assert_eq!(
+            file.to_url("http", IpAddr::V4(Ipv4Addr::LOCALHOST), 1024),
+            format!("http://127.0.0.1:1024/{}", file.key)
+        );
+
+        let dir = map
+            .register_info(Path::new("src"), &LinkOptions::default())
+            .unwrap();
+        assert_eq!(dir.kind, PathKind::Directory);
+        assert_eq!(dir.size, None);
+
+        assert!(map
+            .register_info(Path::new("missing"), &LinkOptions::default())
+            .is_err());
+    }
 }
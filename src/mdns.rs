@@ -0,0 +1,68 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::net::IpAddr;
+
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+use tokio_util::sync::CancellationToken;
+
+/// DNS-SD service type of DirectShare
+pub const SERVICE_TYPE: &str = "_directshare._tcp.local.";
+
+/// Advertise service on local network until shutdown
+pub async fn mdns_service(
+    ip: IpAddr,
+    port: u16,
+    scheme: &'static str,
+    shutdown: CancellationToken,
+) {
+    let hostname = gethostname::gethostname().to_string_lossy().to_string();
+
+    let daemon = match ServiceDaemon::new() {
+        Ok(daemon) => daemon,
+        Err(err) => {
+            log::warn!("cannot start mDNS daemon err: {err}");
+            return;
+        }
+    };
+
+    let info = match ServiceInfo::new(
+        SERVICE_TYPE,
+        &format!("DirectShare on {hostname}"),
+        &format!("{hostname}.local."),
+        ip,
+        port,
+        &[("scheme", scheme)][..],
+    ) {
+        Ok(info) => info,
+        Err(err) => {
+            log::warn!("invalid mDNS service info hostname: {hostname} err: {err}");
+            return;
+        }
+    };
+    let fullname = info.get_fullname().to_string();
+
+    if let Err(err) = daemon.register(info) {
+        log::warn!("mDNS advertisement failed err: {err}");
+        return;
+    }
+    log::info!("advertising mDNS service name: {fullname}");
+
+    shutdown.cancelled().await;
+
+    match daemon.unregister(&fullname) {
+        Ok(receiver) => {
+            let _ = receiver.recv_async().await;
+            log::info!("mDNS advertisement withdrawn name: {fullname}");
+        }
+
+        Err(err) => log::warn!("cannot withdraw mDNS advertisement err: {err}"),
+    }
+
+    if let Ok(receiver) = daemon.shutdown() {
+        let _ = receiver.recv_async().await;
+    }
+}
@@ -0,0 +1,164 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use mime_guess::mime::{self, Mime};
+
+/// Check if browser can display the media type without downloading
+pub fn is_renderable(media_type: &Mime) -> bool {
+    matches!(
+        media_type.type_(),
+        mime::IMAGE | mime::TEXT | mime::VIDEO | mime::AUDIO
+    ) || *media_type == mime::APPLICATION_PDF
+        || *media_type == mime::APPLICATION_JSON
+}
+
+/// Check if the media type benefits from compression
+pub fn is_compressible(media_type: &Mime) -> bool {
+    if media_type.type_() == mime::TEXT {
+        return true;
+    }
+
+    if matches!(media_type.suffix(), Some(mime::JSON) | Some(mime::XML)) {
+        return true;
+    }
+
+    media_type.type_() == mime::APPLICATION
+        && matches!(
+            media_type.subtype().as_str(),
+            "json" | "javascript" | "xml" | "x-sh" | "wasm"
+        )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Content coding used for compressing responses
+pub enum Encoding {
+    Brotli,
+    Gzip,
+}
+
+impl Encoding {
+    /// Supported codings in order of preference
+    pub const ALL: [Encoding; 2] = [Encoding::Brotli, Encoding::Gzip];
+
+    /// Name used in `Content-Encoding` header
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+        }
+    }
+}
+
+/// Check if `Accept-Encoding` header value accepts the content coding
+pub fn accepts_encoding(value: &str, coding: &str) -> bool {
+    encoding_quality(value, coding).is_some_and(|q| q > 0.0)
+}
+
+/// Pick coding with highest quality in `Accept-Encoding` header value.
+/// Brotli is preferred over gzip on tie, `None` if identity is preferred or nothing is accepted
+pub fn preferred_encoding(value: &str) -> Option<Encoding> {
+    let mut preferred: Option<(Encoding, f32)> = None;
+    for encoding in Encoding::ALL {
+        let Some(q) = encoding_quality(value, encoding.as_str()) else {
+            continue;
+        };
+
+        if q > 0.0 && preferred.is_none_or(|(_, preferred_q)| q > preferred_q) {
+            preferred = Some((encoding, q));
+        }
+    }
+
+    let (encoding, q) = preferred?;
+    let identity = encoding_quality(value, "identity").unwrap_or_default();
+    (q >= identity).then_some(encoding)
+}
+
+/// Quality of content coding in `Accept-Encoding` header value.
+/// Explicitly listed coding takes precedence over `*`
+fn encoding_quality(value: &str, coding: &str) -> Option<f32> {
+    let mut wildcard = None;
+    for item in value.split(',') {
+        let mut params = item.split(';');
+
+        let name = params.next().unwrap_or_default().trim();
+        let q = params
+            .find_map(|param| param.trim().strip_prefix("q="))
+            .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok());
+
+        if name.eq_ignore_ascii_case(coding) {
+            return Some(q.unwrap_or_default());
+        } else if name == "*" {
+            wildcard = q;
+        }
+    }
+
+    wildcard
+}
+
+/// Check if `Accept` header value lists html explicitly, as browsers do.
+/// Wildcards sent by command line clients are not counted.
+pub fn accepts_html(value: &str) -> bool {
+    accepts(value, |name| name.eq_ignore_ascii_case("text/html"))
+}
+
+/// Check if any item of accept header value matching `matches` is not rejected with q=0
+fn accepts(value: &str, matches: impl Fn(&str) -> bool) -> bool {
+    value.split(',').any(|item| {
+        let mut params = item.split(';');
+
+        let name = params.next().unwrap_or_default().trim();
+        if !matches(name) {
+            return false;
+        }
+
+        // Reject if explicitly disabled with q=0
+        !params.any(|param| {
+            param
+                .trim()
+                .strip_prefix("q=")
+                .and_then(|q| q.trim().parse::<f32>().ok())
+                .is_some_and(|q| q <= 0.0)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::media::{accepts_encoding, accepts_html, preferred_encoding, Encoding};
+
+    #[test]
+    pub fn accepts_encoding_test() {
+        assert!(accepts_encoding("gzip, deflate, br", "gzip"));
+        assert!(accepts_encoding("GZIP;q=0.5", "gzip"));
+        assert!(accepts_encoding("*", "gzip"));
+        assert!(!accepts_encoding("gzip;q=0", "gzip"));
+        assert!(!accepts_encoding("identity", "gzip"));
+        assert!(!accepts_encoding("gzip;q=0, *", "gzip"));
+    }
+
+    #[test]
+    pub fn preferred_encoding_test() {
+        assert_eq!(
+            preferred_encoding("gzip, deflate, br"),
+            Some(Encoding::Brotli)
+        );
+        assert_eq!(
+            preferred_encoding("br;q=0.5, gzip;q=0.8"),
+            Some(Encoding::Gzip)
+        );
+        assert_eq!(preferred_encoding("br;q=0, *"), Some(Encoding::Gzip));
+        assert_eq!(preferred_encoding("identity, gzip;q=0.5"), None);
+        assert_eq!(preferred_encoding("deflate"), None);
+    }
+
+    #[test]
+    pub fn accepts_html_test() {
+        assert!(accepts_html(
+            "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8"
+        ));
+        assert!(!accepts_html("*/*"));
+    }
+}
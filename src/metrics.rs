@@ -0,0 +1,107 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use hyper::StatusCode;
+
+#[derive(Debug, Default)]
+/// Counters exposed in prometheus text format
+pub struct Metrics {
+    requests: AtomicU64,
+    statuses: Mutex<BTreeMap<u16, u64>>,
+    bytes_served: AtomicU64,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count handled request responded with `status`
+    pub fn record(&self, status: StatusCode) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        *self
+            .statuses
+            .lock()
+            .unwrap()
+            .entry(status.as_u16())
+            .or_default() += 1;
+    }
+
+    /// Count bytes of response body sent
+    pub fn add_bytes(&self, bytes: u64) {
+        self.bytes_served.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Render metrics with current connection and link count
+    pub fn render(&self, active_connections: usize, links: usize) -> String {
+        let mut text = String::new();
+
+        let _ = writeln!(
+            text,
+            "# HELP direct_share_requests_total Total handled requests\n# TYPE direct_share_requests_total counter\ndirect_share_requests_total {}",
+            self.requests.load(Ordering::Relaxed)
+        );
+
+        text.push_str("# HELP direct_share_responses_total Handled requests by status\n# TYPE direct_share_responses_total counter\n");
+        for (status, count) in self.statuses.lock().unwrap().iter() {
+            let _ = writeln!(
+                text,
+                "direct_share_responses_total{{status=\"{status}\"}} {count}"
+            );
+        }
+
+        let _ = writeln!(
+            text,
+            "# HELP direct_share_bytes_served_total Bytes of response bodies sent\n# TYPE direct_share_bytes_served_total counter\ndirect_share_bytes_served_total {}",
+            self.bytes_served.load(Ordering::Relaxed)
+        );
+
+        let _ = writeln!(
+            text,
+            "# HELP direct_share_active_connections Open connections\n# TYPE direct_share_active_connections gauge\ndirect_share_active_connections {active_connections}"
+        );
+
+        let _ = writeln!(
+            text,
+            "# HELP direct_share_links Available links\n# TYPE direct_share_links gauge\ndirect_share_links {links}"
+        );
+
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::StatusCode;
+
+    use crate::metrics::Metrics;
+
+    #[test]
+    pub fn render_test() {
+        let metrics = Metrics::new();
+        metrics.record(StatusCode::OK);
+        metrics.record(StatusCode::OK);
+        metrics.record(StatusCode::NOT_FOUND);
+        metrics.add_bytes(1024);
+
+        let text = metrics.render(3, 5);
+        assert!(text.contains("direct_share_requests_total 3\n"));
+        assert!(text.contains("direct_share_responses_total{status=\"200\"} 2\n"));
+        assert!(text.contains("direct_share_responses_total{status=\"404\"} 1\n"));
+        assert!(text.contains("direct_share_bytes_served_total 1024\n"));
+        assert!(text.contains("direct_share_active_connections 3\n"));
+        assert!(text.contains("direct_share_links 5\n"));
+    }
+}
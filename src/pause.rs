@@ -0,0 +1,70 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[derive(Debug, Default)]
+/// Paused state of server. File requests are answered with 503 while paused
+pub struct Pause {
+    paused: AtomicBool,
+}
+
+impl Pause {
+    pub const fn new() -> Self {
+        Self {
+            paused: AtomicBool::new(false),
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Acquire)
+    }
+
+    /// Set paused state. Returns `true` if the state changed
+    pub fn set(&self, paused: bool) -> bool {
+        let changed = self.paused.swap(paused, Ordering::AcqRel) != paused;
+        if changed {
+            log_state(paused);
+        }
+
+        changed
+    }
+
+    /// Flip paused state and return the new state
+    pub fn toggle(&self) -> bool {
+        let paused = !self.paused.fetch_xor(true, Ordering::AcqRel);
+        log_state(paused);
+
+        paused
+    }
+}
+
+fn log_state(paused: bool) {
+    if paused {
+        log::info!("sharing paused, file requests are answered with 503");
+    } else {
+        log::info!("sharing resumed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pause::Pause;
+
+    #[test]
+    pub fn pause_test() {
+        let pause = Pause::new();
+        assert!(!pause.is_paused());
+
+        assert!(pause.set(true));
+        assert!(!pause.set(true));
+        assert!(pause.is_paused());
+
+        assert!(!pause.toggle());
+        assert!(!pause.is_paused());
+        assert!(pause.toggle());
+    }
+}
@@ -0,0 +1,158 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Byte range of resource. Both ends are inclusive
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ByteRange {
+    /// Length of the range in bytes
+    pub const fn content_length(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    /// Value for `Content-Range` header
+    pub fn content_range(&self, total: u64) -> String {
+        format!("bytes {}-{}/{total}", self.start, self.end)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RangeError {
+    #[error("malformed range")]
+    Malformed,
+    #[error("range not satisfiable")]
+    Unsatisfiable,
+}
+
+/// Parse `Range` header value for resource with `len` bytes.
+///
+/// Returns `Ok(None)` if the range should be ignored and full content should be served.
+/// Multiple ranges are not supported and ignored.
+pub fn parse_range(value: &str, len: u64) -> Result<Option<ByteRange>, RangeError> {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        // Unknown range unit
+        return Ok(None);
+    };
+
+    if spec.contains(',') {
+        return Ok(None);
+    }
+
+    let (start, end) = spec.trim().split_once('-').ok_or(RangeError::Malformed)?;
+    let (start, end) = (start.trim(), end.trim());
+
+    let range = if start.is_empty() {
+        // Suffix range, last N bytes
+        let suffix: u64 = end.parse().map_err(|_| RangeError::Malformed)?;
+        if suffix == 0 || len == 0 {
+            return Err(RangeError::Unsatisfiable);
+        }
+
+        ByteRange {
+            start: len.saturating_sub(suffix),
+            end: len - 1,
+        }
+    } else {
+        let start: u64 = start.parse().map_err(|_| RangeError::Malformed)?;
+        let end = if end.is_empty() {
+            u64::MAX
+        } else {
+            end.parse().map_err(|_| RangeError::Malformed)?
+        };
+
+        if end < start {
+            return Err(RangeError::Malformed);
+        }
+
+        if start >= len {
+            return Err(RangeError::Unsatisfiable);
+        }
+
+        ByteRange {
+            start,
+            end: end.min(len - 1),
+        }
+    };
+
+    Ok(Some(range))
+}
+
+/// Parse `Range` header value resuming resource of unknown length.
+/// Returns start offset of `bytes=N-` range, `None` for any other range.
+pub fn parse_resume(value: &str) -> Option<u64> {
+    let start = value
+        .trim()
+        .strip_prefix("bytes=")?
+        .trim()
+        .strip_suffix('-')?
+        .trim();
+
+    start.parse().ok().filter(|&start| start > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::range::{parse_range, parse_resume, ByteRange, RangeError};
+
+    #[test]
+    pub fn parse_resume_test() {
+        assert_eq!(parse_resume("bytes=1024-"), Some(1024));
+        assert_eq!(parse_resume("bytes=0-"), None);
+        assert_eq!(parse_resume("bytes=0-99"), None);
+        assert_eq!(parse_resume("bytes=-100"), None);
+        assert_eq!(parse_resume("bytes=5-,10-"), None);
+    }
+
+    #[test]
+    pub fn parse_range_test() {
+        assert_eq!(
+            parse_range("bytes=0-99", 1000).unwrap(),
+            Some(ByteRange { start: 0, end: 99 })
+        );
+        assert_eq!(
+            parse_range("bytes=900-", 1000).unwrap(),
+            Some(ByteRange {
+                start: 900,
+                end: 999
+            })
+        );
+        assert_eq!(
+            parse_range("bytes=-100", 1000).unwrap(),
+            Some(ByteRange {
+                start: 900,
+                end: 999
+            })
+        );
+        assert_eq!(
+            parse_range("bytes=500-5000", 1000).unwrap(),
+            Some(ByteRange {
+                start: 500,
+                end: 999
+            })
+        );
+        assert_eq!(parse_range("bytes=0-1,5-6", 1000).unwrap(), None);
+        assert_eq!(parse_range("items=0-1", 1000).unwrap(), None);
+
+        assert!(matches!(
+            parse_range("bytes=1000-", 1000),
+            Err(RangeError::Unsatisfiable)
+        ));
+        assert!(matches!(
+            parse_range("bytes=5-1", 1000),
+            Err(RangeError::Malformed)
+        ));
+        assert!(matches!(
+            parse_range("bytes=abc", 1000),
+            Err(RangeError::Malformed)
+        ));
+    }
+}
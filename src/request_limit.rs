@@ -0,0 +1,87 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::{collections::HashMap, net::IpAddr, num::NonZeroU32, sync::Mutex, time::Duration};
+
+use tokio::time::Instant;
+
+#[derive(Debug)]
+/// Limits request count of each remote ip in fixed time window
+pub struct RequestLimiter {
+    limit: NonZeroU32,
+    window: Duration,
+
+    /// Start of current window and request count in it
+    clients: Mutex<HashMap<IpAddr, (Instant, u32)>>,
+}
+
+impl RequestLimiter {
+    pub fn new(limit: NonZeroU32, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub const fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Count a request from `ip`. Returns `false` if the ip exceeded the limit.
+    pub fn check(&self, ip: IpAddr) -> bool {
+        let now = Instant::now();
+        let mut clients = self.clients.lock().unwrap();
+
+        let (start, count) = clients.entry(ip).or_insert((now, 0));
+        if now.duration_since(*start) >= self.window {
+            *start = now;
+            *count = 0;
+        }
+
+        *count = count.saturating_add(1);
+        *count <= self.limit.get()
+    }
+
+    /// Forget clients whose window has ended
+    pub fn remove_stale(&self) {
+        let now = Instant::now();
+
+        self.clients
+            .lock()
+            .unwrap()
+            .retain(|_, (start, _)| now.duration_since(*start) < self.window);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        num::NonZeroU32,
+        time::Duration,
+    };
+
+    use crate::request_limit::RequestLimiter;
+
+    #[tokio::test(start_paused = true)]
+    pub async fn request_limiter_test() {
+        let limiter = RequestLimiter::new(NonZeroU32::new(2).unwrap(), Duration::from_secs(10));
+        let a = IpAddr::V4(Ipv4Addr::new(192, 168, 0, 2));
+        let b = IpAddr::V4(Ipv4Addr::new(192, 168, 0, 3));
+
+        assert!(limiter.check(a));
+        assert!(limiter.check(a));
+        assert!(!limiter.check(a));
+        assert!(limiter.check(b));
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert!(limiter.check(a));
+
+        limiter.remove_stale();
+        assert_eq!(limiter.clients.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,48 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::{io, path::PathBuf};
+
+/// Name of the shortcut shown in Send To menu
+pub const SHORTCUT_NAME: &str = "DirectShare.cmd";
+
+/// Install Windows "Send To" menu shortcut launching current executable with selected files.
+/// Returns path of the created shortcut.
+#[cfg(windows)]
+pub fn install() -> io::Result<PathBuf> {
+    use std::{env, fs, io::ErrorKind};
+
+    let exe = env::current_exe()?;
+    let exe_dir = exe
+        .parent()
+        .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "executable directory not found"))?;
+
+    let app_data = env::var_os("APPDATA")
+        .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "APPDATA is not set"))?;
+    let send_to = PathBuf::from(app_data).join(r"Microsoft\Windows\SendTo");
+    fs::create_dir_all(&send_to)?;
+
+    // Run next to the executable so its direct_share.toml is used
+    let script = format!(
+        "@echo off\r\ncd /d \"{}\"\r\n\"{}\" %*\r\n",
+        exe_dir.display(),
+        exe.display()
+    );
+
+    let path = send_to.join(SHORTCUT_NAME);
+    fs::write(&path, script)?;
+
+    Ok(path)
+}
+
+/// Send To menu only exists on Windows
+#[cfg(not(windows))]
+pub fn install() -> io::Result<PathBuf> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "send to menu is only available on Windows",
+    ))
+}
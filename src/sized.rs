@@ -0,0 +1,107 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::{
+    io::{self, ErrorKind},
+    path::PathBuf,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf, Take};
+
+#[derive(Debug)]
+/// Reader yielding exactly the length sent as `Content-Length`.
+/// Fails with [`ErrorKind::UnexpectedEof`] if the file was truncated, and cuts off bytes appended while serving.
+pub struct SizedReader<R> {
+    inner: Take<R>,
+    path: PathBuf,
+    len: u64,
+    checked: bool,
+}
+
+impl<R: AsyncRead> SizedReader<R> {
+    pub fn new(inner: R, len: u64, path: PathBuf) -> Self {
+        Self {
+            inner: inner.take(len),
+            path,
+            len,
+            checked: false,
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for SizedReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        let filled = buf.filled().len();
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+        if buf.filled().len() != filled || buf.remaining() == 0 || this.checked {
+            return Poll::Ready(Ok(()));
+        }
+
+        let remaining = this.inner.limit();
+        if remaining != 0 {
+            log::warn!(
+                "file shrank while serving path: {} len: {} sent: {}",
+                this.path.display(),
+                this.len,
+                this.len - remaining
+            );
+
+            return Poll::Ready(Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "file shrank while serving",
+            )));
+        }
+
+        // Read one more byte to find out if the file grew
+        let mut extra = [0; 1];
+        let mut extra = ReadBuf::new(&mut extra);
+        ready!(Pin::new(this.inner.get_mut()).poll_read(cx, &mut extra))?;
+
+        this.checked = true;
+        if !extra.filled().is_empty() {
+            log::warn!(
+                "file grew while serving, sending original length path: {} len: {}",
+                this.path.display(),
+                this.len
+            );
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use tokio::io::AsyncReadExt;
+
+    use crate::sized::SizedReader;
+
+    #[tokio::test]
+    pub async fn sized_reader_test() {
+        let mut data = Vec::new();
+        SizedReader::new(&b"hello world"[..], 5, "grown".into())
+            .read_to_end(&mut data)
+            .await
+            .unwrap();
+        assert_eq!(data, b"hello");
+
+        let err = SizedReader::new(&b"hi"[..], 5, "shrunk".into())
+            .read_to_end(&mut Vec::new())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,129 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::{
+    io,
+    num::NonZeroU64,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures_util::{Stream, StreamExt};
+use hyper::body::Bytes;
+use tokio::time::{sleep_until, Instant};
+
+#[derive(Debug)]
+/// Limits byte rate of streams sharing the limiter
+pub struct RateLimiter {
+    /// Bytes per second
+    rate: NonZeroU64,
+
+    /// Time when next bytes can be sent
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(rate: NonZeroU64) -> Self {
+        Self {
+            rate,
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub const fn rate(&self) -> NonZeroU64 {
+        self.rate
+    }
+
+    /// Wait until `bytes` can be sent
+    pub async fn acquire(&self, bytes: u64) {
+        let cost = Duration::from_secs_f64(bytes as f64 / self.rate.get() as f64);
+
+        let start = {
+            let mut next = self.next.lock().unwrap();
+
+            let start = (*next).max(Instant::now());
+            *next = start + cost;
+
+            start
+        };
+
+        sleep_until(start).await;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// Rate limiters applied to a connection
+pub struct Throttle {
+    limiters: Vec<Arc<RateLimiter>>,
+}
+
+impl Throttle {
+    /// Create throttle limited by both `global` limiter and `per_connection` rate
+    pub fn new(global: Option<Arc<RateLimiter>>, per_connection: Option<NonZeroU64>) -> Self {
+        Self {
+            limiters: global
+                .into_iter()
+                .chain(per_connection.map(|rate| Arc::new(RateLimiter::new(rate))))
+                .collect(),
+        }
+    }
+
+    pub fn is_limited(&self) -> bool {
+        !self.limiters.is_empty()
+    }
+
+    /// Chunk size not exceeding `max` which keeps pacing smooth for the lowest rate
+    pub fn chunk_size(&self, max: usize) -> usize {
+        self.limiters
+            .iter()
+            .map(|limiter| (limiter.rate().get() / 10).max(1) as usize)
+            .fold(max, usize::min)
+    }
+
+    /// Pace chunks of the stream
+    pub fn wrap<S>(&self, stream: S) -> impl Stream<Item = io::Result<Bytes>> + Send + Sync
+    where
+        S: Stream<Item = io::Result<Bytes>> + Send + Sync,
+    {
+        let limiters: Arc<[Arc<RateLimiter>]> = self.limiters.clone().into();
+
+        stream.then(move |chunk| {
+            let limiters = limiters.clone();
+
+            async move {
+                if let Ok(ref data) = chunk {
+                    for limiter in limiters.iter() {
+                        limiter.acquire(data.len() as u64).await;
+                    }
+                }
+
+                chunk
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{num::NonZeroU64, time::Duration};
+
+    use tokio::time::Instant;
+
+    use crate::throttle::RateLimiter;
+
+    #[tokio::test(start_paused = true)]
+    pub async fn rate_limiter_test() {
+        let limiter = RateLimiter::new(NonZeroU64::new(1000).unwrap());
+
+        let start = Instant::now();
+        for _ in 0..5 {
+            limiter.acquire(500).await;
+        }
+
+        // Last chunk is sent after previous 2000 bytes
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+    }
+}
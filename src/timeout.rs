@@ -0,0 +1,145 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::{
+    future::Future,
+    io::{self, ErrorKind, IoSlice},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    time::{sleep, Instant, Sleep},
+};
+
+#[derive(Debug)]
+/// Stream failing with [`ErrorKind::TimedOut`] once no bytes are read or written for the duration
+pub struct IdleTimeout<I> {
+    inner: I,
+    timeout: Option<Duration>,
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl<I> IdleTimeout<I> {
+    /// Wrap stream. Timeout is disabled if `timeout` is `None`.
+    pub fn new(inner: I, timeout: Option<Duration>) -> Self {
+        Self {
+            inner,
+            timeout,
+            sleep: timeout.map(|timeout| Box::pin(sleep(timeout))),
+        }
+    }
+
+    fn reset(&mut self) {
+        if let (Some(sleep), Some(timeout)) = (&mut self.sleep, self.timeout) {
+            sleep.as_mut().reset(Instant::now() + timeout);
+        }
+    }
+
+    /// Convert pending io into error if the stream has been idle for too long
+    fn poll_idle<T>(
+        &mut self,
+        cx: &mut Context<'_>,
+        poll: Poll<io::Result<T>>,
+    ) -> Poll<io::Result<T>> {
+        match poll {
+            Poll::Ready(res) => {
+                self.reset();
+                Poll::Ready(res)
+            }
+
+            Poll::Pending => {
+                let expired = self
+                    .sleep
+                    .as_mut()
+                    .is_some_and(|sleep| sleep.as_mut().poll(cx).is_ready());
+
+                if expired {
+                    Poll::Ready(Err(io::Error::new(ErrorKind::TimedOut, "connection idle")))
+                } else {
+                    Poll::Pending
+                }
+            }
+        }
+    }
+}
+
+impl<I: AsyncRead + Unpin> AsyncRead for IdleTimeout<I> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_read(cx, buf);
+
+        this.poll_idle(cx, poll)
+    }
+}
+
+impl<I: AsyncWrite + Unpin> AsyncWrite for IdleTimeout<I> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_write(cx, buf);
+
+        this.poll_idle(cx, poll)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_write_vectored(cx, bufs);
+
+        this.poll_idle(cx, poll)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_flush(cx);
+
+        this.poll_idle(cx, poll)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::ErrorKind, time::Duration};
+
+    use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt};
+
+    use crate::timeout::IdleTimeout;
+
+    #[tokio::test(start_paused = true)]
+    pub async fn idle_timeout_test() {
+        let (client, server) = duplex(64);
+        let mut client = client;
+        let mut server = IdleTimeout::new(server, Some(Duration::from_secs(10)));
+
+        let mut buf = [0; 4];
+        client.write_all(b"ping").await.unwrap();
+        server.read_exact(&mut buf).await.unwrap();
+
+        let err = server.read(&mut buf).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+    }
+}
@@ -0,0 +1,198 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::{
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use http_body_util::combinators::BoxBody;
+use hyper::body::{Body, Bytes, Frame, SizeHint};
+
+use crate::{listing::format_size, zero_copy::Sent};
+
+/// Response body logging transfer stats when the transfer ends
+pub struct LoggedBody {
+    inner: BoxBody<Bytes, io::Error>,
+
+    key: String,
+    addr: SocketAddr,
+
+    /// Content length of the response if known
+    length: Option<u64>,
+
+    start: Instant,
+    sent: u64,
+    finished: bool,
+
+    /// Bytes sent by zero copy response, its frames are counted before being sent
+    zero_copy: Option<Sent>,
+}
+
+impl LoggedBody {
+    pub fn new(
+        inner: BoxBody<Bytes, io::Error>,
+        length: Option<u64>,
+        key: String,
+        addr: SocketAddr,
+    ) -> Self {
+        Self {
+            inner,
+            key,
+            addr,
+            length,
+            start: Instant::now(),
+            sent: 0,
+            finished: false,
+            zero_copy: None,
+        }
+    }
+
+    /// Log bytes of zero copy response counted by `sent` instead of body frames
+    pub fn with_zero_copy(mut self, sent: Sent) -> Self {
+        self.zero_copy = Some(sent);
+        self
+    }
+
+    fn complete(&mut self) {
+        self.finished = true;
+        log::info!(
+            "transfer completed key: {} addr: {} {}",
+            self.key,
+            self.addr,
+            self.stats()
+        );
+    }
+
+    fn stats(&self) -> String {
+        let sent = self.zero_copy.as_ref().map_or(self.sent, Sent::get);
+
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let speed = if elapsed > 0.0 {
+            (sent as f64 / elapsed) as u64
+        } else {
+            sent
+        };
+
+        format!(
+            "sent: {} duration: {elapsed:.2}s speed: {}/s",
+            format_size(sent),
+            format_size(speed)
+        )
+    }
+}
+
+impl Body for LoggedBody {
+    type Data = Bytes;
+    type Error = io::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let res = Pin::new(&mut self.inner).poll_frame(cx);
+
+        match res {
+            Poll::Ready(Some(Ok(ref frame))) => {
+                if let Some(data) = frame.data_ref() {
+                    self.sent += data.len() as u64;
+                }
+
+                // Body is not polled again once content length is reached
+                if !self.finished && self.length.is_some_and(|length| self.sent >= length) {
+                    self.complete();
+                }
+            }
+
+            Poll::Ready(Some(Err(ref err))) => {
+                self.finished = true;
+                log::warn!(
+                    "transfer failed key: {} addr: {} {} err: {err}",
+                    self.key,
+                    self.addr,
+                    self.stats()
+                );
+            }
+
+            Poll::Ready(None) => {
+                if !self.finished {
+                    self.complete();
+                }
+            }
+
+            Poll::Pending => {}
+        }
+
+        res
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl Drop for LoggedBody {
+    fn drop(&mut self) {
+        if !self.finished {
+            log::warn!(
+                "transfer aborted key: {} addr: {} {}",
+                self.key,
+                self.addr,
+                self.stats()
+            );
+        }
+    }
+}
+
+/// Body calling `on_end` once dropped, for accounting responses sent outside of body frames
+pub struct EndBody<F: FnOnce()> {
+    inner: BoxBody<Bytes, io::Error>,
+    on_end: Option<F>,
+}
+
+impl<F: FnOnce()> EndBody<F> {
+    pub fn new(inner: BoxBody<Bytes, io::Error>, on_end: F) -> Self {
+        Self {
+            inner,
+            on_end: Some(on_end),
+        }
+    }
+}
+
+impl<F: FnOnce() + Unpin> Body for EndBody<F> {
+    type Data = Bytes;
+    type Error = io::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        Pin::new(&mut self.inner).poll_frame(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl<F: FnOnce()> Drop for EndBody<F> {
+    fn drop(&mut self) {
+        if let Some(on_end) = self.on_end.take() {
+            on_end();
+        }
+    }
+}
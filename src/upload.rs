@@ -0,0 +1,285 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::{
+    io::{self, ErrorKind},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+
+use http_body_util::{combinators::BoxBody, BodyExt, Empty, Full};
+use hyper::{
+    body::{Body, Bytes},
+    header, Method, Request, Response, StatusCode,
+};
+use serde::Serialize;
+use thiserror::Error;
+use tokio::{
+    fs::{self, File, OpenOptions},
+    io::{AsyncWriteExt, BufWriter},
+};
+
+use crate::{config::DirectShareConfig, constants::FILE_BUF_SIZE};
+
+/// Page posting selected file to the upload endpoint
+const UPLOAD_FORM: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Upload</title>
+</head>
+<body>
+<h1>Upload</h1>
+<input id="file" type="file">
+<button id="send">Send</button>
+<p id="status"></p>
+<script>
+document.getElementById("send").onclick = async () => {
+    const file = document.getElementById("file").files[0];
+    const status = document.getElementById("status");
+    if (!file) return;
+
+    status.textContent = "uploading...";
+    const res = await fetch("upload?name=" + encodeURIComponent(file.name), { method: "POST", body: file });
+    status.textContent = res.ok ? "saved as " + (await res.json()).name : "upload failed: " + res.status;
+};
+</script>
+</body>
+</html>
+"#;
+
+#[derive(Debug, Error)]
+pub enum UploadError {
+    #[error("upload exceeds size limit")]
+    TooLarge,
+
+    #[error("invalid filename")]
+    InvalidName,
+
+    #[error(transparent)]
+    Body(#[from] hyper::Error),
+
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+#[derive(Debug, Serialize)]
+struct UploadResponse {
+    name: String,
+}
+
+/// Handle request to upload page
+pub async fn handle(
+    addr: SocketAddr,
+    config: &DirectShareConfig,
+    req: Request<hyper::body::Incoming>,
+) -> Response<BoxBody<Bytes, io::Error>> {
+    match *req.method() {
+        Method::GET | Method::HEAD => Response::builder()
+            .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+            .body(
+                Full::new(Bytes::from_static(UPLOAD_FORM.as_bytes()))
+                    .map_err(|_| unreachable!())
+                    .boxed(),
+            )
+            .unwrap(),
+
+        Method::POST => receive(addr, config, req).await,
+
+        _ => status_response(StatusCode::METHOD_NOT_ALLOWED),
+    }
+}
+
+async fn receive(
+    addr: SocketAddr,
+    config: &DirectShareConfig,
+    req: Request<hyper::body::Incoming>,
+) -> Response<BoxBody<Bytes, io::Error>> {
+    let declared = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+
+    if declared.is_some_and(|len| len > config.max_upload_size) {
+        log::warn!("upload rejected, too large addr: {addr}");
+        return status_response(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    let name = req.uri().query().and_then(|query| {
+        form_urlencoded::parse(query.as_bytes())
+            .find(|(name, _)| name == "name")
+            .map(|(_, value)| value.into_owned())
+    });
+
+    match save(
+        &config.upload_dir,
+        name.as_deref().unwrap_or_default(),
+        req.into_body(),
+        config.max_upload_size,
+    )
+    .await
+    {
+        Ok(path) => {
+            log::info!("received upload path: {} addr: {addr}", path.display());
+
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            Response::builder()
+                .status(StatusCode::CREATED)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(
+                    Full::new(Bytes::from(
+                        serde_json::to_vec(&UploadResponse { name }).unwrap(),
+                    ))
+                    .map_err(|_| unreachable!())
+                    .boxed(),
+                )
+                .unwrap()
+        }
+
+        Err(err) => {
+            log::warn!("upload failed addr: {addr} err: {err}");
+
+            status_response(match err {
+                UploadError::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+                UploadError::InvalidName | UploadError::Body(_) => StatusCode::BAD_REQUEST,
+                UploadError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            })
+        }
+    }
+}
+
+/// Stream body to a new file named `name` in `dir`.
+/// Fails once more than `max_size` bytes are received, partially written file is removed on failure.
+pub async fn save<B: Body<Data = Bytes, Error = hyper::Error> + Unpin>(
+    dir: &Path,
+    name: &str,
+    mut body: B,
+    max_size: u64,
+) -> Result<PathBuf, UploadError> {
+    fs::create_dir_all(dir).await?;
+    let (path, file) = create_unique(dir, name).await?;
+
+    let mut writer = BufWriter::with_capacity(FILE_BUF_SIZE, file);
+    let res: Result<(), UploadError> = async {
+        let mut size = 0;
+        while let Some(frame) = body.frame().await {
+            let Ok(data) = frame?.into_data() else {
+                continue;
+            };
+
+            size += data.len() as u64;
+            if size > max_size {
+                return Err(UploadError::TooLarge);
+            }
+
+            writer.write_all(&data).await?;
+        }
+
+        writer.flush().await?;
+        Ok(())
+    }
+    .await;
+
+    if let Err(err) = res {
+        drop(writer);
+        let _ = fs::remove_file(&path).await;
+        return Err(err);
+    }
+
+    Ok(path)
+}
+
+/// Create new file in `dir` using base name of `name`, suffixing number on collision
+async fn create_unique(dir: &Path, name: &str) -> Result<(PathBuf, File), UploadError> {
+    let name = Path::new(name)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .filter(|name| !name.chars().any(char::is_control))
+        .ok_or(UploadError::InvalidName)?;
+
+    let mut unique = name.clone();
+    let mut count = 1;
+    loop {
+        let path = dir.join(&unique);
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .await
+        {
+            Ok(file) => return Ok((path, file)),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        unique = match name.rsplit_once('.') {
+            Some((stem, ext)) if !stem.is_empty() => format!("{stem} ({count}).{ext}"),
+            _ => format!("{name} ({count})"),
+        };
+        count += 1;
+    }
+}
+
+fn status_response(status: StatusCode) -> Response<BoxBody<Bytes, io::Error>> {
+    Response::builder()
+        .status(status)
+        .body(Empty::<Bytes>::new().map_err(|_| unreachable!()).boxed())
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::stream;
+    use http_body_util::StreamBody;
+    use hyper::body::{Bytes, Frame};
+
+    use crate::upload::{create_unique, save, UploadError};
+
+    #[tokio::test]
+    pub async fn create_unique_test() {
+        let dir = std::env::temp_dir().join("direct_share_upload_test");
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+
+        let (first, _) = create_unique(&dir, "../../photo.jpg").await.unwrap();
+        let (second, _) = create_unique(&dir, "photo.jpg").await.unwrap();
+
+        assert_eq!(first, dir.join("photo.jpg"));
+        assert_eq!(second, dir.join("photo (1).jpg"));
+        assert!(create_unique(&dir, "..").await.is_err());
+        assert!(create_unique(&dir, "").await.is_err());
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    pub async fn save_limit_test() {
+        let dir = std::env::temp_dir().join("direct_share_upload_limit_test");
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+
+        let body = |chunks: usize| {
+            StreamBody::new(stream::iter(
+                (0..chunks).map(|_| Ok::<_, hyper::Error>(Frame::data(Bytes::from(vec![0; 8])))),
+            ))
+        };
+
+        let path = save(&dir, "fits.bin", body(2), 16).await.unwrap();
+        assert_eq!(tokio::fs::metadata(&path).await.unwrap().len(), 16);
+
+        assert!(matches!(
+            save(&dir, "large.bin", body(3), 16).await,
+            Err(UploadError::TooLarge)
+        ));
+        assert!(!tokio::fs::try_exists(dir.join("large.bin")).await.unwrap());
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
@@ -0,0 +1,54 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use crate::constants::FALLBACK_FILENAME;
+
+/// Make file name safe to send to recipients.
+/// Control characters like CR/LF are removed and path separators `/`, `\` are replaced with `_`.
+/// Quotes are kept, they are escaped when the name is quoted.
+pub fn sanitize_filename(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .filter(|ch| !ch.is_control())
+        .map(|ch| match ch {
+            '/' | '\\' => '_',
+            ch => ch,
+        })
+        .collect();
+
+    let name = name.trim();
+    if name.is_empty() {
+        FALLBACK_FILENAME.to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        constants::FALLBACK_FILENAME, disposition::content_disposition, util::sanitize_filename,
+    };
+
+    #[test]
+    pub fn sanitize_filename_test() {
+        assert_eq!(sanitize_filename("report.pdf"), "report.pdf");
+        assert_eq!(
+            sanitize_filename("evil\r\nSet-Cookie: a=b.txt"),
+            "evilSet-Cookie: a=b.txt"
+        );
+        assert_eq!(sanitize_filename("../etc/passwd"), ".._etc_passwd");
+        assert_eq!(sanitize_filename("..\\a\\b.txt"), ".._a_b.txt");
+        assert_eq!(sanitize_filename(" \n\t"), FALLBACK_FILENAME);
+
+        // Quotes and semicolons cannot break out of header value
+        assert_eq!(sanitize_filename("a\"; b.txt"), "a\"; b.txt");
+        assert_eq!(
+            content_disposition(false, &sanitize_filename("a\"; name=b.exe")).unwrap(),
+            "attachment; filename=\"a_; name=b.exe\"; filename*=UTF-8''a%22%3B%20name%3Db.exe"
+        );
+    }
+}
@@ -0,0 +1,431 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+//! Zero copy file transfer using `sendfile` on Linux.
+//!
+//! File region of the response is kept in [`SendFileSlot`] shared by response body and connection stream.
+//! hyper hands only byte slices to the stream, so body yields placeholder chunks marking where the
+//! region goes in the output. Stream sends the pending chunk from the file with `sendfile` when it
+//! is asked to write the placeholder, and fails the connection if hyper flushes without doing so.
+//! Next chunk is not yielded until the previous one is sent and the last byte of the file is sent
+//! as regular data, so the response never completes unless whole region went through `sendfile`.
+//! hyper must keep body chunks unflattened (`writev`) for the placeholders to reach the stream.
+
+use std::{
+    fs::File,
+    io::{self, SeekFrom},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+};
+
+use http_body_util::{combinators::BoxBody, BodyExt};
+use hyper::body::{Body, Bytes, Frame, SizeHint};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// Size of placeholder chunk
+const CHUNK_SIZE: usize = 1 << 20;
+
+static PLACEHOLDER: [u8; CHUNK_SIZE] = [0; CHUNK_SIZE];
+
+#[derive(Debug, Clone, Default)]
+/// Bytes of zero copy response actually sent, stored in response extensions.
+/// Body frames are placeholders, so they do not tell how much was sent
+pub struct Sent(Arc<AtomicU64>);
+
+impl Sent {
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Acquire)
+    }
+
+    fn add(&self, bytes: u64) {
+        self.0.fetch_add(bytes, Ordering::AcqRel);
+    }
+
+    fn is_same(&self, other: &Sent) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+/// Placeholder chunk waiting to be sent by the connection
+struct Chunk {
+    len: usize,
+
+    /// Bytes of the chunk already sent
+    sent: usize,
+}
+
+#[derive(Debug)]
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+/// File region being sent by the connection
+struct Region {
+    file: File,
+
+    /// File offset of next byte to send
+    offset: u64,
+
+    chunk: Option<Chunk>,
+    sent: Sent,
+
+    /// Body waiting for the chunk to be sent
+    waker: Option<Waker>,
+}
+
+#[derive(Debug, Clone, Default)]
+/// File region of current zero copy response, shared between response body and connection stream
+pub struct SendFileSlot(Arc<Mutex<Option<Region>>>);
+
+/// Body sending first `len` bytes of `file` through the connection of `slot`.
+/// Returns the body with its count of sent bytes
+pub async fn body(
+    mut file: tokio::fs::File,
+    len: u64,
+    slot: SendFileSlot,
+) -> io::Result<(BoxBody<Bytes, io::Error>, Sent)> {
+    let tail = if len > 0 {
+        let mut byte = [0; 1];
+        file.seek(SeekFrom::Start(len - 1)).await?;
+        file.read_exact(&mut byte).await?;
+
+        Some(Bytes::copy_from_slice(&byte))
+    } else {
+        None
+    };
+
+    let sent = Sent::default();
+    let body = ZeroCopyBody {
+        slot,
+        file: Some(file.into_std().await),
+        remaining: len.saturating_sub(1),
+        tail,
+        sent: sent.clone(),
+    };
+
+    Ok((body.boxed(), sent))
+}
+
+/// Body yielding placeholder chunks of file region one by one and the last byte of the file
+struct ZeroCopyBody {
+    slot: SendFileSlot,
+
+    /// File handed to the connection on first poll
+    file: Option<File>,
+
+    /// Length of region not yielded yet
+    remaining: u64,
+
+    /// Last byte sent after the region
+    tail: Option<Bytes>,
+
+    sent: Sent,
+}
+
+impl Body for ZeroCopyBody {
+    type Data = Bytes;
+    type Error = io::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.get_mut();
+        if this.remaining == 0 && this.tail.is_none() {
+            return Poll::Ready(None);
+        }
+
+        let mut slot = this.slot.0.lock().unwrap();
+        if let Some(file) = this.file.take() {
+            *slot = Some(Region {
+                file,
+                offset: 0,
+                chunk: None,
+                sent: this.sent.clone(),
+                waker: None,
+            });
+        }
+
+        let Some(region) = slot
+            .as_mut()
+            .filter(|region| region.sent.is_same(&this.sent))
+        else {
+            return Poll::Ready(Some(Err(io::Error::other("zero copy region was replaced"))));
+        };
+
+        if region.chunk.is_some() {
+            region.waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        if this.remaining > 0 {
+            let len = this.remaining.min(CHUNK_SIZE as u64) as usize;
+            region.chunk = Some(Chunk { len, sent: 0 });
+            this.remaining -= len as u64;
+
+            return Poll::Ready(Some(Ok(Frame::data(Bytes::from_static(
+                &PLACEHOLDER[..len],
+            )))));
+        }
+
+        // Whole region is sent, file is not needed anymore
+        *slot = None;
+
+        let tail = this.tail.take().unwrap();
+        this.sent.add(tail.len() as u64);
+        Poll::Ready(Some(Ok(Frame::data(tail))))
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::with_exact(
+            self.remaining + self.tail.as_ref().map_or(0, |tail| tail.len() as u64),
+        )
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use linux::ZeroCopyStream;
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::{
+        io::{self, ErrorKind, IoSlice},
+        os::fd::AsRawFd,
+        pin::Pin,
+        task::{ready, Context, Poll},
+    };
+
+    use tokio::{
+        io::{AsyncRead, AsyncWrite, Interest, ReadBuf},
+        net::TcpStream,
+    };
+
+    use super::{SendFileSlot, CHUNK_SIZE, PLACEHOLDER};
+
+    /// Check if `buf` is part of placeholder chunk
+    fn is_placeholder(buf: &[u8]) -> bool {
+        let start = PLACEHOLDER.as_ptr() as usize;
+        let ptr = buf.as_ptr() as usize;
+
+        !buf.is_empty() && ptr >= start && ptr + buf.len() <= start + CHUNK_SIZE
+    }
+
+    #[derive(Debug)]
+    /// Tcp stream sending placeholder chunks of zero copy body with `sendfile`
+    pub struct ZeroCopyStream {
+        inner: TcpStream,
+        slot: SendFileSlot,
+    }
+
+    impl ZeroCopyStream {
+        pub fn new(inner: TcpStream) -> Self {
+            Self {
+                inner,
+                slot: SendFileSlot::default(),
+            }
+        }
+
+        pub fn slot(&self) -> SendFileSlot {
+            self.slot.clone()
+        }
+
+        fn poll_send_file(&self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+            let mut slot = self.slot.0.lock().unwrap();
+            let Some(region) = slot.as_mut() else {
+                return Poll::Ready(Err(io::Error::other("zero copy chunk without file")));
+            };
+
+            let Some(ref mut chunk) = region.chunk else {
+                return Poll::Ready(Err(io::Error::other("zero copy chunk is already sent")));
+            };
+
+            // Chunk must be written in order from where it was left
+            if buf.as_ptr() != PLACEHOLDER[chunk.sent..].as_ptr()
+                || buf.len() > chunk.len - chunk.sent
+            {
+                return Poll::Ready(Err(io::Error::other(
+                    "zero copy chunk is written out of order",
+                )));
+            }
+
+            loop {
+                ready!(self.inner.poll_write_ready(cx))?;
+
+                let res = self.inner.try_io(Interest::WRITABLE, || {
+                    let mut offset = region.offset as libc::off_t;
+
+                    // SAFETY: both descriptors are open and offset is a valid pointer
+                    let sent = unsafe {
+                        libc::sendfile(
+                            self.inner.as_raw_fd(),
+                            region.file.as_raw_fd(),
+                            &mut offset,
+                            buf.len(),
+                        )
+                    };
+
+                    if sent < 0 {
+                        Err(io::Error::last_os_error())
+                    } else {
+                        Ok(sent as usize)
+                    }
+                });
+
+                match res {
+                    Ok(0) => {
+                        return Poll::Ready(Err(io::Error::new(
+                            ErrorKind::UnexpectedEof,
+                            "file shrank while sending",
+                        )))
+                    }
+
+                    Ok(sent) => {
+                        region.offset += sent as u64;
+                        region.sent.add(sent as u64);
+
+                        chunk.sent += sent;
+                        if chunk.sent == chunk.len {
+                            region.chunk = None;
+                            if let Some(waker) = region.waker.take() {
+                                waker.wake();
+                            }
+                        }
+
+                        return Poll::Ready(Ok(sent));
+                    }
+
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => continue,
+                    Err(err) => return Poll::Ready(Err(err)),
+                }
+            }
+        }
+    }
+
+    impl AsyncRead for ZeroCopyStream {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.get_mut().inner).poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncWrite for ZeroCopyStream {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            if is_placeholder(buf) {
+                return self.poll_send_file(cx, buf);
+            }
+
+            Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)
+        }
+
+        fn poll_write_vectored(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            bufs: &[IoSlice<'_>],
+        ) -> Poll<io::Result<usize>> {
+            // Bytes before placeholder are written first, caller retries with the rest
+            match bufs.iter().position(|buf| is_placeholder(buf)) {
+                Some(0) => self.poll_send_file(cx, &bufs[0]),
+
+                Some(index) => {
+                    Pin::new(&mut self.get_mut().inner).poll_write_vectored(cx, &bufs[..index])
+                }
+
+                None => Pin::new(&mut self.get_mut().inner).poll_write_vectored(cx, bufs),
+            }
+        }
+
+        fn is_write_vectored(&self) -> bool {
+            true
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            // hyper flushes once everything it queued is written,
+            // so pending chunk went out some other way than sendfile
+            let unsent = self
+                .slot
+                .0
+                .lock()
+                .unwrap()
+                .as_ref()
+                .is_some_and(|region| region.chunk.is_some());
+            if unsent {
+                return Poll::Ready(Err(io::Error::other(
+                    "zero copy chunk was not sent with sendfile",
+                )));
+            }
+
+            Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use http_body_util::BodyExt;
+    use tokio::{
+        fs::{self, File},
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+
+    use crate::zero_copy::{body, ZeroCopyStream};
+
+    #[tokio::test]
+    pub async fn send_file_test() {
+        let path = std::env::temp_dir().join("direct_share_send_file_test.txt");
+        fs::write(&path, "hello world").await.unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let mut stream = ZeroCopyStream::new(listener.accept().await.unwrap().0);
+
+        let file = File::open(&path).await.unwrap();
+        let (mut res, sent) = body(file, 11, stream.slot()).await.unwrap();
+
+        // Placeholder is sent from the file, last byte as regular data
+        let chunk = res.frame().await.unwrap().unwrap().into_data().unwrap();
+        stream.write_all(&chunk).await.unwrap();
+        stream.flush().await.unwrap();
+        assert_eq!(sent.get(), 10);
+
+        let tail = res.frame().await.unwrap().unwrap().into_data().unwrap();
+        stream.write_all(&tail).await.unwrap();
+        assert_eq!(sent.get(), 11);
+        assert!(res.frame().await.is_none());
+
+        let mut data = [0; 11];
+        client.read_exact(&mut data).await.unwrap();
+        assert_eq!(&data, b"hello world");
+
+        // Copied placeholder fails the connection instead of being sent as content
+        let file = File::open(&path).await.unwrap();
+        let (mut res, sent) = body(file, 11, stream.slot()).await.unwrap();
+
+        let chunk = res.frame().await.unwrap().unwrap().into_data().unwrap();
+        let copy = chunk.to_vec();
+        stream.write_all(&copy).await.unwrap();
+        assert!(stream.flush().await.is_err());
+        assert_eq!(sent.get(), 0);
+
+        fs::remove_file(&path).await.unwrap();
+    }
+}
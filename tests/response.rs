@@ -0,0 +1,570 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * Copyright (c) storycraft. Licensed under the MIT Licence.
+ */
+
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    path::PathBuf,
+    time::Duration,
+};
+
+use direct_share::{
+    app,
+    config::{ArchiveFormat, DirectShareConfig},
+    DirectShare,
+};
+use http_body_util::{BodyExt, Empty};
+use hyper::{
+    body::Bytes, client::conn::http1, header, header::HeaderName, HeaderMap, Method, Request,
+    StatusCode,
+};
+use hyper_util::rt::TokioIo;
+use tokio::{
+    fs,
+    io::duplex,
+    net::TcpStream,
+    spawn,
+    time::{sleep, timeout},
+};
+
+/// Temp directory containing `hello.txt` and `dir/inner.txt`
+async fn fixture(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("direct_share_response_test_{name}"));
+    let _ = fs::remove_dir_all(&root).await;
+
+    fs::create_dir_all(root.join("dir")).await.unwrap();
+    fs::write(root.join("hello.txt"), "hello world")
+        .await
+        .unwrap();
+    fs::write(root.join("dir").join("inner.txt"), "inner")
+        .await
+        .unwrap();
+
+    root
+}
+
+fn share() -> DirectShare {
+    DirectShare::builder(DirectShareConfig::default()).build()
+}
+
+/// Send request over in memory connection
+async fn request(share: &DirectShare, method: Method, uri: &str) -> (StatusCode, HeaderMap, Bytes) {
+    request_with(share, method, uri, &[]).await
+}
+
+/// Send request with extra headers over in memory connection
+async fn request_with(
+    share: &DirectShare,
+    method: Method,
+    uri: &str,
+    headers: &[(HeaderName, &str)],
+) -> (StatusCode, HeaderMap, Bytes) {
+    let (client, server) = duplex(64 * 1024);
+
+    let share = share.clone();
+    spawn(async move {
+        share
+            .serve_io(server, SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 1024))
+            .await
+    });
+
+    let (mut sender, conn) = http1::handshake(TokioIo::new(client)).await.unwrap();
+    spawn(conn);
+
+    let mut builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::HOST, "localhost");
+    for (name, value) in headers {
+        builder = builder.header(name, *value);
+    }
+
+    let res = sender
+        .send_request(builder.body(Empty::<Bytes>::new()).unwrap())
+        .await
+        .unwrap();
+
+    let status = res.status();
+    let headers = res.headers().clone();
+    let body = res.into_body().collect().await.unwrap().to_bytes();
+
+    (status, headers, body)
+}
+
+#[tokio::test]
+pub async fn serve_file_test() {
+    let root = fixture("file").await;
+    let share = share();
+    let key = share.register(root.join("hello.txt"));
+
+    let (status, headers, body) = request(&share, Method::GET, &format!("/{key}")).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "hello world");
+    assert_eq!(headers[header::CONTENT_LENGTH], "11");
+    assert_eq!(
+        headers[header::CONTENT_DISPOSITION],
+        "attachment; filename=\"hello.txt\"; filename*=UTF-8''hello.txt"
+    );
+
+    fs::remove_dir_all(root).await.unwrap();
+}
+
+#[tokio::test]
+pub async fn serve_directory_test() {
+    let root = fixture("directory").await;
+    let share = share();
+    let key = share.register(root.join("dir"));
+
+    let (status, headers, body) = request(&share, Method::GET, &format!("/{key}")).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers[header::CONTENT_TYPE], "application/x-tar");
+    assert_eq!(
+        headers[header::CONTENT_DISPOSITION],
+        "attachment; filename=\"dir.tar\"; filename*=UTF-8''dir.tar"
+    );
+    assert!(body.windows(5).any(|window| window == b"inner"));
+
+    fs::remove_dir_all(root).await.unwrap();
+}
+
+#[tokio::test]
+pub async fn download_limit_test() {
+    let root = fixture("download_limit").await;
+    let share = DirectShare::builder(DirectShareConfig {
+        default_download_limit: Some(1),
+        ..Default::default()
+    })
+    .build();
+    let key = share.register(root.join("hello.txt"));
+    let uri = format!("/{key}");
+
+    // HEAD and not modified responses are not counted
+    let (status, headers, _) = request(&share, Method::HEAD, &uri).await;
+    assert_eq!(status, StatusCode::OK);
+    let etag = headers[header::ETAG].to_str().unwrap().to_string();
+
+    let (status, _, _) =
+        request_with(&share, Method::GET, &uri, &[(header::IF_NONE_MATCH, &etag)]).await;
+    assert_eq!(status, StatusCode::NOT_MODIFIED);
+
+    // Ranges are ignored, so the whole file is sent and counted
+    let (status, headers, body) =
+        request_with(&share, Method::GET, &uri, &[(header::RANGE, "bytes=0-0")]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(!headers.contains_key(header::CONTENT_RANGE));
+    assert_eq!(body, "hello world");
+
+    let (status, _, _) =
+        request_with(&share, Method::GET, &uri, &[(header::RANGE, "bytes=1-")]).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _, _) = request(&share, Method::GET, &uri).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    fs::remove_dir_all(root).await.unwrap();
+}
+
+#[tokio::test]
+pub async fn archive_resume_test() {
+    let root = fixture("archive_resume").await;
+
+    let share = share();
+    let key = share.register(root.join("dir"));
+
+    let (status, _, full) = request(&share, Method::GET, &format!("/{key}")).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let range = [(header::RANGE, "bytes=100-")];
+    let (status, headers, body) =
+        request_with(&share, Method::GET, &format!("/{key}"), &range).await;
+    assert_eq!(status, StatusCode::PARTIAL_CONTENT);
+    assert_eq!(
+        headers[header::CONTENT_RANGE],
+        format!("bytes 100-{}/{}", full.len() - 1, full.len())
+    );
+    assert_eq!(body, full[100..]);
+
+    // Size of zip is unknown, so the whole archive is sent
+    let share = DirectShare::builder(DirectShareConfig {
+        archive_format: ArchiveFormat::Zip,
+        ..Default::default()
+    })
+    .build();
+    let key = share.register(root.join("dir"));
+
+    let (status, headers, _) = request_with(&share, Method::GET, &format!("/{key}"), &range).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(!headers.contains_key(header::CONTENT_RANGE));
+
+    fs::remove_dir_all(root).await.unwrap();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+pub async fn symlink_test() {
+    let root = fixture("symlink").await;
+    std::os::unix::fs::symlink(root.join("hello.txt"), root.join("file_link")).unwrap();
+    std::os::unix::fs::symlink(root.join("dir"), root.join("dir_link")).unwrap();
+
+    let share = share();
+
+    let file = share.register_info(&root.join("file_link")).unwrap();
+    assert_eq!(
+        file.path,
+        fs::canonicalize(root.join("hello.txt")).await.unwrap()
+    );
+
+    let (status, headers, body) = request(&share, Method::GET, &format!("/{}", file.key)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        headers[header::CONTENT_DISPOSITION],
+        "attachment; filename=\"hello.txt\"; filename*=UTF-8''hello.txt"
+    );
+    assert_eq!(body, "hello world");
+
+    let dir = share.register_info(&root.join("dir_link")).unwrap();
+    assert_eq!(dir.path, fs::canonicalize(root.join("dir")).await.unwrap());
+
+    let (status, _, body) = request(&share, Method::GET, &format!("/{}", dir.key)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.windows(5).any(|window| window == b"inner"));
+
+    fs::remove_dir_all(root).await.unwrap();
+}
+
+#[tokio::test]
+pub async fn content_digest_test() {
+    let root = fixture("content_digest").await;
+    let share = DirectShare::builder(DirectShareConfig {
+        send_digest: true,
+        ..Default::default()
+    })
+    .build();
+    let key = share.register(root.join("hello.txt"));
+
+    let (status, headers, _) = request(&share, Method::GET, &format!("/{key}")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        headers["content-digest"],
+        "sha-256=:uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=:"
+    );
+
+    let (status, headers, _) = request(&share, Method::HEAD, &format!("/{key}")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(headers.contains_key("content-digest"));
+
+    fs::remove_dir_all(root).await.unwrap();
+}
+
+#[tokio::test]
+pub async fn strong_etag_test() {
+    let root = fixture("strong_etag").await;
+    let share = DirectShare::builder(DirectShareConfig {
+        strong_etags: true,
+        ..Default::default()
+    })
+    .build();
+    let key = share.register(root.join("hello.txt"));
+
+    for method in [Method::GET, Method::HEAD] {
+        let (status, headers, _) = request(&share, method, &format!("/{key}")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            headers[header::ETAG],
+            "\"b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9\""
+        );
+    }
+
+    fs::remove_dir_all(root).await.unwrap();
+}
+
+#[tokio::test]
+pub async fn checksum_test() {
+    let root = fixture("checksum").await;
+    let share = DirectShare::builder(DirectShareConfig {
+        checksums: true,
+        ..Default::default()
+    })
+    .build();
+    let key = share.register(root.join("hello.txt"));
+    let dir_key = share.register(root.join("dir"));
+
+    let (status, headers, body) = request(&share, Method::GET, &format!("/{key}.sha256")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers[header::CONTENT_TYPE], "text/plain; charset=utf-8");
+    assert_eq!(
+        body,
+        "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  hello.txt\n"
+    );
+
+    let (status, _, _) = request(&share, Method::GET, &format!("/{dir_key}.sha256")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _, body) = request(&share, Method::GET, &format!("/{key}")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "hello world");
+
+    fs::remove_dir_all(root).await.unwrap();
+}
+
+#[tokio::test]
+pub async fn zero_copy_test() {
+    let root = fixture("zero_copy").await;
+    let large: Vec<u8> = (0..3 * 1024 * 1024 + 7).map(|i| (i % 251) as u8).collect();
+    fs::write(root.join("large.bin"), &large).await.unwrap();
+
+    let share = DirectShare::builder(DirectShareConfig {
+        zero_copy: true,
+        access_log: Some(root.join("access.log")),
+        ..Default::default()
+    })
+    .build();
+    let large_key = share.register(root.join("large.bin"));
+    let key = share.register(root.join("hello.txt"));
+
+    let listener = app::bind(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0)).unwrap();
+    let addr = listener.local_addr().unwrap();
+    spawn(share.clone().serve(listener));
+
+    let metrics_listener = app::bind(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0)).unwrap();
+    let metrics_addr = metrics_listener.local_addr().unwrap();
+    spawn(share.serve_metrics(metrics_listener));
+
+    // Both responses are sent over one keep-alive connection
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let (mut sender, conn) = http1::handshake(TokioIo::new(stream)).await.unwrap();
+    spawn(conn);
+
+    for (key, expected) in [
+        (large_key, large.as_slice()),
+        (key, b"hello world".as_slice()),
+    ] {
+        let res = sender
+            .send_request(
+                Request::builder()
+                    .uri(format!("/{key}"))
+                    .header(header::HOST, "localhost")
+                    .body(Empty::<Bytes>::new())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, expected);
+    }
+
+    // Bytes sent with sendfile are counted
+    let sent = (large.len() + 11).to_string();
+    let stream = TcpStream::connect(metrics_addr).await.unwrap();
+    let (mut sender, conn) = http1::handshake(TokioIo::new(stream)).await.unwrap();
+    spawn(conn);
+    let res = sender
+        .send_request(
+            Request::builder()
+                .uri("/metrics")
+                .header(header::HOST, "localhost")
+                .body(Empty::<Bytes>::new())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = res.into_body().collect().await.unwrap().to_bytes();
+    assert!(String::from_utf8_lossy(&body)
+        .lines()
+        .any(|line| line == format!("direct_share_bytes_served_total {sent}")));
+
+    let log = timeout(Duration::from_secs(5), async {
+        loop {
+            let log = fs::read_to_string(root.join("access.log"))
+                .await
+                .unwrap_or_default();
+            if log.lines().count() == 2 {
+                break log;
+            }
+
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    assert!(log
+        .lines()
+        .any(|line| line.ends_with(&format!(" 200 {}", large.len()))));
+    assert!(log.lines().any(|line| line.ends_with(" 200 11")));
+
+    fs::remove_dir_all(root).await.unwrap();
+}
+
+#[tokio::test]
+pub async fn not_found_test() {
+    let root = fixture("not_found").await;
+    let share = share();
+    let key = share.register(root.join("hello.txt"));
+
+    let (status, _, _) = request(&share, Method::GET, "/unknown").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    for method in [Method::POST, Method::PUT, Method::DELETE] {
+        let (status, _, _) = request(&share, method, &format!("/{key}")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    fs::remove_dir_all(root).await.unwrap();
+}
+
+#[tokio::test]
+pub async fn pause_test() {
+    let root = fixture("pause").await;
+    let share = share();
+    let key = share.register(root.join("hello.txt"));
+
+    share.pause().set(true);
+    let (status, _, body) = request(&share, Method::GET, &format!("/{key}")).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert!(body.is_empty());
+
+    share.pause().set(false);
+    let (status, _, body) = request(&share, Method::GET, &format!("/{key}")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "hello world");
+
+    fs::remove_dir_all(root).await.unwrap();
+}
+
+#[tokio::test]
+pub async fn maintenance_file_test() {
+    let root = fixture("maintenance").await;
+    fs::write(root.join("maintenance.html"), "back soon")
+        .await
+        .unwrap();
+
+    let share = DirectShare::builder(DirectShareConfig {
+        maintenance_file: Some(root.join("maintenance.html").to_string_lossy().into()),
+        ..Default::default()
+    })
+    .build();
+    let key = share.register(root.join("hello.txt"));
+    share.pause().set(true);
+
+    let (status, headers, body) = request(&share, Method::GET, &format!("/{key}")).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(headers[header::CONTENT_TYPE], "text/html");
+    assert_eq!(body, "back soon");
+
+    fs::remove_file(root.join("maintenance.html"))
+        .await
+        .unwrap();
+    let (status, _, body) = request(&share, Method::GET, &format!("/{key}")).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert!(body.is_empty());
+
+    fs::remove_dir_all(root).await.unwrap();
+}
+
+#[test]
+pub fn build_validated_test() {
+    let share = DirectShare::builder(DirectShareConfig {
+        path_prefix: Some("/share/".into()),
+        rate_limit_window_secs: 0,
+        file_buffer_size: 0,
+        ..Default::default()
+    })
+    .build();
+
+    let config = share.config();
+    assert_eq!(config.path_prefix.as_deref(), Some("share"));
+    assert_eq!(config.rate_limit_window_secs, 1);
+    assert_ne!(config.file_buffer_size, 0);
+}
+
+#[tokio::test]
+pub async fn path_prefix_test() {
+    let root = fixture("prefix").await;
+    let share = DirectShare::builder(DirectShareConfig {
+        path_prefix: Some("share".into()),
+        ..Default::default()
+    })
+    .build();
+    let key = share.register(root.join("hello.txt"));
+
+    let (status, _, body) = request(&share, Method::GET, &format!("/share/{key}")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "hello world");
+
+    let (status, _, _) = request(&share, Method::GET, &format!("/{key}")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    fs::remove_dir_all(root).await.unwrap();
+}
+
+#[tokio::test]
+pub async fn options_test() {
+    let root = fixture("options").await;
+    let share = DirectShare::builder(DirectShareConfig {
+        cors_allow_origin: Some("*".into()),
+        ..Default::default()
+    })
+    .build();
+    let key = share.register(root.join("hello.txt"));
+
+    let (status, headers, _) = request(&share, Method::OPTIONS, &format!("/{key}")).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_eq!(headers[header::ALLOW], "GET, HEAD, OPTIONS");
+
+    let (status, headers, _) = request(&share, Method::GET, &format!("/{key}")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+
+    fs::remove_dir_all(root).await.unwrap();
+}
+
+#[tokio::test]
+pub async fn favicon_test() {
+    let (status, headers, body) = request(&share(), Method::GET, "/favicon.ico").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers[header::CONTENT_TYPE], "image/x-icon");
+    assert!(!body.is_empty());
+}
+
+#[test]
+pub fn concurrent_map_test() {
+    let share = share();
+    let shared = share.register(PathBuf::from("Cargo.toml"));
+
+    let writers: Vec<_> = (0..4)
+        .map(|_| {
+            let share = share.clone();
+            std::thread::spawn(move || {
+                for i in 0..500 {
+                    let key = share.register(PathBuf::from(format!("file{i}")));
+                    assert_eq!(share.get(&key), Some(PathBuf::from(format!("file{i}"))));
+                    assert!(share.unregister(&key).is_some());
+                }
+            })
+        })
+        .collect();
+
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let share = share.clone();
+            let shared = shared.clone();
+            std::thread::spawn(move || {
+                for _ in 0..2000 {
+                    assert_eq!(share.get(&shared), Some(PathBuf::from("Cargo.toml")));
+                }
+            })
+        })
+        .collect();
+
+    for handle in writers.into_iter().chain(readers) {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(share.map().read().unwrap().len(), 1);
+}